@@ -0,0 +1,96 @@
+// Compare the Rc<RefCell> BST with the arena BST at 10^6 keys
+// Run with: cargo run --release --example arena_bench
+use std::time::{Duration, Instant};
+
+use binarysearchtree::structure::arena::ArenaBst;
+use binarysearchtree::structure::bst::{Bst, BstNode};
+use binarysearchtree::structure::treap::SplitMix64;
+
+const KEY_COUNT: usize = 1_000_000;
+
+// Keys shuffled with the treap's seeded generator, random order keeps both trees shallow
+fn shuffled_keys(count: usize) -> Vec<i32> {
+    let mut keys: Vec<i32> = (0..count as i32).collect();
+    let mut rng = SplitMix64::new(2024);
+    for i in (1..keys.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        keys.swap(i, j);
+    }
+    keys
+}
+
+fn time<T>(label: &str, f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    println!("{:<28}{:>10.1} ms", label, elapsed.as_secs_f64() * 1000.0);
+    (result, elapsed)
+}
+
+fn main() {
+    let keys = shuffled_keys(KEY_COUNT);
+    println!("{} shuffled keys", KEY_COUNT);
+
+    println!("-- bst (Rc<RefCell<BstNode>>)");
+    let mut rc_total = Duration::ZERO;
+    let (mut rc_tree, elapsed) = time("insert", || {
+        let mut tree = Bst::new();
+        for &key in &keys {
+            tree.insert(key);
+        }
+        tree
+    });
+    rc_total += elapsed;
+    let (found, elapsed) = time("search", || keys.iter().filter(|key| rc_tree.contains(key)).count());
+    rc_total += elapsed;
+    assert_eq!(found, KEY_COUNT);
+    let (walked, elapsed) = time("successor walk", || {
+        let mut count = 0;
+        let mut current = rc_tree.root().map(BstNode::minimum);
+        while let Some(node) = current {
+            count += 1;
+            current = BstNode::tree_successor(&node);
+        }
+        count
+    });
+    rc_total += elapsed;
+    assert_eq!(walked, KEY_COUNT);
+    let (_, elapsed) = time("delete", || {
+        for key in &keys {
+            rc_tree.remove(key);
+        }
+    });
+    rc_total += elapsed;
+    assert!(rc_tree.is_empty());
+
+    println!("-- arena (Vec + u32 links)");
+    let mut arena_total = Duration::ZERO;
+    let (mut arena_tree, elapsed) = time("insert", || {
+        let mut tree = ArenaBst::with_capacity(KEY_COUNT);
+        for &key in &keys {
+            tree.insert(key);
+        }
+        tree
+    });
+    arena_total += elapsed;
+    let (found, elapsed) = time("search", || keys.iter().filter(|key| arena_tree.contains(key)).count());
+    arena_total += elapsed;
+    assert_eq!(found, KEY_COUNT);
+    let (walked, elapsed) = time("successor walk", || arena_tree.iter().count());
+    arena_total += elapsed;
+    assert_eq!(walked, KEY_COUNT);
+    let (_, elapsed) = time("delete", || {
+        for key in &keys {
+            arena_tree.remove(key);
+        }
+    });
+    arena_total += elapsed;
+    assert!(arena_tree.is_empty());
+
+    println!(
+        "total: bst {:.1} ms, arena {:.1} ms ({:.1}x)",
+        rc_total.as_secs_f64() * 1000.0,
+        arena_total.as_secs_f64() * 1000.0,
+        rc_total.as_secs_f64() / arena_total.as_secs_f64()
+    );
+}
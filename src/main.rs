@@ -1,19 +1,57 @@
-mod structure;
-mod tool;
-
-use crate::structure::bst::BstNode;
-use crate::structure::tree::Node;
-use crate::structure::tree::NodeLink;
-use crate::structure::bst::BstNodeLink;
-use crate::tool::generate_dotfile;
-use crate::tool::generate_dotfile_bst;
+use binarysearchtree::structure::bst::Bst;
+use binarysearchtree::structure::bst::BstNode;
+use binarysearchtree::structure::tree::Node;
+use binarysearchtree::structure::tree::NodeLink;
+use binarysearchtree::structure::bst::BstNodeLink;
+use binarysearchtree::structure::bst_map::BstMap;
+use binarysearchtree::tool::generate_dotfile;
+use binarysearchtree::tool::generate_dotfile_bst;
 
 fn main() {
     // Uncomment to test the old tree structure code
     // test_binary_tree();
     test_binary_search_tree();
+    test_bst_map();
+    test_bst_handle();
+}
+
+fn test_bst_handle() {
+    let mut tree: Bst = Bst::new();
+    for key in [42, 17, 68, 9, 33] {
+        tree.insert(key);
+    }
+    println!("Bst length: {}, min: {:?}, max: {:?}", tree.len(), tree.min(), tree.max());
+
+    // Removing the root through the handle keeps the root link up to date
+    println!("Removed 42: {}", tree.remove(&42));
+    println!("Removed 42 again: {}", tree.remove(&42));
+    println!("Contains 33: {}", tree.contains(&33));
+    println!("Bst length after removal: {}", tree.len());
+
+    tree.clear();
+    println!("Bst is empty after clear: {}", tree.is_empty());
+}
+
+fn test_bst_map() {
+    let mut map: BstMap<String, usize> = BstMap::new();
+    for word in "the quick brown fox jumps over the lazy dog".split_whitespace() {
+        // insert returns the previous value, so repeated words are visible here
+        if let Some(old) = map.insert(word.to_string(), word.len()) {
+            println!("Key {} already present with value {}", word, old);
+        }
+    }
+    println!("Map length: {}", map.len());
+
+    if let Some(mut value) = map.get_mut(&"fox".to_string()) {
+        *value *= 10;
+    }
+    println!("Value of fox: {:?}", map.get(&"fox".to_string()));
+    println!("Removed the: {:?}", map.remove(&"the".to_string()));
+    println!("Contains the: {}", map.contains_key(&"the".to_string()));
+    println!("Map length after removal: {}", map.len());
 }
 
+#[allow(clippy::useless_vec)]
 fn test_binary_search_tree() {
     // Create a root node with key 15
     let rootlink: BstNodeLink = BstNode::new_bst_nodelink(15);
@@ -72,7 +110,7 @@ fn test_binary_search_tree() {
     for &key in search_keys.iter() {
         print!("Tree search result for key {}: ", key);
         if let Some(ref node) = rootlink {
-            if let Some(node_result) = node.borrow().tree_search(&key) {
+            if let Some(node_result) = BstNode::tree_search(node, &key) {
                 println!("Found -> {:?}", node_result.borrow().key);
             } else {
                 println!("Not found");
@@ -85,10 +123,10 @@ fn test_binary_search_tree() {
 
     // Get the minimum and maximum values in the tree
     if let Some(ref node) = rootlink {
-        let min_node = node.borrow().minimum();
+        let min_node = BstNode::minimum(node);
         println!("Minimum value: {:?}", min_node.borrow().key);
 
-        let max_node = node.borrow().maximum();
+        let max_node = BstNode::maximum(node);
         println!("Maximum value: {:?}", max_node.borrow().key);
     } else {
         println!("Tree is empty, cannot get min or max");
@@ -96,7 +134,7 @@ fn test_binary_search_tree() {
 
     // Test getting the root node
     if let Some(ref node) = rootlink {
-        let max_node = node.borrow().maximum();
+        let max_node = BstNode::maximum(node);
         let root_node = BstNode::get_root(&max_node);
         println!("Root node: {:?}", root_node.borrow().key);
     }
@@ -113,9 +151,9 @@ fn test_binary_search_tree() {
 
     for &key in query_keys.iter() {
         if let Some(ref node_rc) = rootlink {
-            if let Some(node) = node_rc.borrow().tree_search(&key) {
+            if let Some(node) = BstNode::tree_search(node_rc, &key) {
                 print!("Successor of node ({}) is ", key);
-                match BstNode::tree_successor_simpler(&node) {
+                match BstNode::tree_successor(&node) {
                     Some(successor) => println!("{:?}", successor.borrow().key),
                     None => println!("No successor found"),
                 }
@@ -129,6 +167,7 @@ fn test_binary_search_tree() {
 }
 
 #[allow(dead_code)]
+#[allow(clippy::needless_borrow)]
 fn test_binary_tree() {
     // Create the root node of the binary tree
     let rootlink: NodeLink = Node::new_nodelink(5);
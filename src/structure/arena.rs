@@ -0,0 +1,448 @@
+use std::cmp::Ordering;
+
+/**
+ * Handle to a node of an `ArenaBst`: an index into its node vector plus the generation
+ * of that slot. Like a `BstNodeLink` it keeps pointing at the same key while other keys
+ * are inserted or deleted. Deleting the key bumps the slot's generation, so the id goes
+ * stale and stays stale even after a later insert reuses the slot
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+// Index used as the null link
+const NIL: u32 = u32::MAX;
+
+#[derive(Debug, Clone)]
+struct ArenaNode<K> {
+    // None for a slot on the free list
+    key: Option<K>,
+    // bumped every time the slot is freed, ids of older generations are stale
+    generation: u32,
+    parent: u32,
+    left: u32,
+    // next free slot while the slot is on the free list
+    right: u32,
+}
+
+/**
+ * BST whose nodes live in one `Vec` and link to each other by `u32` index instead of
+ * `Rc<RefCell<..>>` and `Weak` parents. Deleted slots go on a free list and are reused
+ * by later inserts. The operations follow `BstNode`: equal keys go to the right,
+ * delete is the CLRS transplant delete, and successor/predecessor climb the parent links
+ */
+#[derive(Debug, Clone)]
+pub struct ArenaBst<K> {
+    nodes: Vec<ArenaNode<K>>,
+    root: u32,
+    free_head: u32,
+    len: usize,
+}
+
+impl<K: Ord> ArenaBst<K> {
+    pub fn new() -> Self {
+        ArenaBst::with_capacity(0)
+    }
+
+    // Empty tree with room for capacity nodes before the vector grows
+    pub fn with_capacity(capacity: usize) -> Self {
+        ArenaBst {
+            nodes: Vec::with_capacity(capacity),
+            root: NIL,
+            free_head: NIL,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn root(&self) -> Option<NodeId> {
+        self.link(self.root)
+    }
+
+    // Whether node still holds its key, false once the key was deleted
+    pub fn is_live(&self, node: NodeId) -> bool {
+        self.nodes.get(node.index as usize).is_some_and(|slot| slot.generation == node.generation && slot.key.is_some())
+    }
+
+    // Key stored in node. This and the other lookups taking a NodeId panic on a stale id
+    pub fn key(&self, node: NodeId) -> &K {
+        self.nodes[self.slot(node) as usize].key.as_ref().unwrap()
+    }
+
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        self.link(self.nodes[self.slot(node) as usize].parent)
+    }
+
+    pub fn left(&self, node: NodeId) -> Option<NodeId> {
+        self.link(self.nodes[self.slot(node) as usize].left)
+    }
+
+    pub fn right(&self, node: NodeId) -> Option<NodeId> {
+        self.link(self.nodes[self.slot(node) as usize].right)
+    }
+
+    // Insert a key and return its node, equal keys go to the right like BstNode::insert
+    pub fn insert(&mut self, key: K) -> NodeId {
+        let mut parent = NIL;
+        let mut go_left = false;
+        let mut current = self.root;
+        while current != NIL {
+            parent = current;
+            let node = &self.nodes[current as usize];
+            go_left = key < *node.key.as_ref().unwrap();
+            current = if go_left { node.left } else { node.right };
+        }
+
+        let new_node = self.allocate(key, parent);
+        if parent == NIL {
+            self.root = new_node;
+        } else if go_left {
+            self.nodes[parent as usize].left = new_node;
+        } else {
+            self.nodes[parent as usize].right = new_node;
+        }
+        self.len += 1;
+        self.link(new_node).unwrap()
+    }
+
+    // Node holding key, if any
+    pub fn tree_search(&self, key: &K) -> Option<NodeId> {
+        let mut current = self.root;
+        while current != NIL {
+            let node = &self.nodes[current as usize];
+            current = match key.cmp(node.key.as_ref().unwrap()) {
+                Ordering::Equal => return self.link(current),
+                Ordering::Less => node.left,
+                Ordering::Greater => node.right,
+            };
+        }
+        None
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree_search(key).is_some()
+    }
+
+    // Node with the smallest key in the subtree rooted at node
+    pub fn minimum(&self, node: NodeId) -> NodeId {
+        let mut current = self.slot(node);
+        while self.nodes[current as usize].left != NIL {
+            current = self.nodes[current as usize].left;
+        }
+        self.link(current).unwrap()
+    }
+
+    // Node with the largest key in the subtree rooted at node
+    pub fn maximum(&self, node: NodeId) -> NodeId {
+        let mut current = self.slot(node);
+        while self.nodes[current as usize].right != NIL {
+            current = self.nodes[current as usize].right;
+        }
+        self.link(current).unwrap()
+    }
+
+    // In-order successor: leftmost node of the right subtree, or the first ancestor reached from the left
+    pub fn tree_successor(&self, node: NodeId) -> Option<NodeId> {
+        if let Some(right) = self.right(node) {
+            return Some(self.minimum(right));
+        }
+        let mut child = self.slot(node);
+        let mut parent = self.nodes[child as usize].parent;
+        while parent != NIL && self.nodes[parent as usize].right == child {
+            child = parent;
+            parent = self.nodes[parent as usize].parent;
+        }
+        self.link(parent)
+    }
+
+    // Mirror of tree_successor
+    pub fn tree_predecessor(&self, node: NodeId) -> Option<NodeId> {
+        if let Some(left) = self.left(node) {
+            return Some(self.maximum(left));
+        }
+        let mut child = self.slot(node);
+        let mut parent = self.nodes[child as usize].parent;
+        while parent != NIL && self.nodes[parent as usize].left == child {
+            child = parent;
+            parent = self.nodes[parent as usize].parent;
+        }
+        self.link(parent)
+    }
+
+    /**
+     * Delete node z and return its key (CLRS 12.3), the successor node is moved into
+     * z's place, so every other NodeId keeps pointing at its own key
+     * z's slot goes on the free list. A stale z returns None and leaves the tree alone
+     */
+    pub fn delete(&mut self, z: NodeId) -> Option<K> {
+        if !self.is_live(z) {
+            return None;
+        }
+        let z = z.index;
+        let (z_left, z_right) = (self.nodes[z as usize].left, self.nodes[z as usize].right);
+        if z_left == NIL {
+            self.transplant(z, z_right);
+        } else if z_right == NIL {
+            self.transplant(z, z_left);
+        } else {
+            let y = self.minimum(self.link(z_right).unwrap()).index;
+            if self.nodes[y as usize].parent != z {
+                let y_right = self.nodes[y as usize].right;
+                self.transplant(y, y_right);
+                self.nodes[y as usize].right = z_right;
+                self.nodes[z_right as usize].parent = y;
+            }
+            self.transplant(z, y);
+            self.nodes[y as usize].left = z_left;
+            self.nodes[z_left as usize].parent = y;
+        }
+        self.len -= 1;
+        Some(self.release(z))
+    }
+
+    // Delete one node holding key, returns false if there is none
+    pub fn remove(&mut self, key: &K) -> bool {
+        match self.tree_search(key) {
+            Some(node) => {
+                self.delete(node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // All keys in order
+    pub fn iter(&self) -> ArenaIter<'_, K> {
+        ArenaIter {
+            tree: self,
+            next: self.root().map(|root| self.minimum(root)),
+        }
+    }
+
+    // Drop every key, all slots go on the free list so the old ids go stale
+    pub fn clear(&mut self) {
+        for slot in (0..self.nodes.len() as u32).rev() {
+            if self.nodes[slot as usize].key.is_some() {
+                self.release(slot);
+            }
+        }
+        self.root = NIL;
+        self.len = 0;
+    }
+
+    // Slot of a live node, panics on a stale id
+    fn slot(&self, node: NodeId) -> u32 {
+        assert!(self.is_live(node), "stale NodeId {:?}", node);
+        node.index
+    }
+
+    // Id of the live slot at index, None for NIL
+    fn link(&self, index: u32) -> Option<NodeId> {
+        if index == NIL {
+            None
+        } else {
+            Some(NodeId {
+                index,
+                generation: self.nodes[index as usize].generation,
+            })
+        }
+    }
+
+    // Hang v (possibly NIL) where u hangs, u's own links are left alone
+    fn transplant(&mut self, u: u32, v: u32) {
+        let u_parent = self.nodes[u as usize].parent;
+        if u_parent == NIL {
+            self.root = v;
+        } else if self.nodes[u_parent as usize].left == u {
+            self.nodes[u_parent as usize].left = v;
+        } else {
+            self.nodes[u_parent as usize].right = v;
+        }
+        if v != NIL {
+            self.nodes[v as usize].parent = u_parent;
+        }
+    }
+
+    // Take a slot from the free list (its generation was bumped on release), or push a new one
+    fn allocate(&mut self, key: K, parent: u32) -> u32 {
+        if self.free_head != NIL {
+            let slot = self.free_head;
+            let node = &mut self.nodes[slot as usize];
+            self.free_head = node.right;
+            node.key = Some(key);
+            node.parent = parent;
+            node.left = NIL;
+            node.right = NIL;
+            slot
+        } else {
+            let slot = u32::try_from(self.nodes.len()).ok().filter(|&slot| slot != NIL).expect("arena is full");
+            self.nodes.push(ArenaNode {
+                key: Some(key),
+                generation: 0,
+                parent,
+                left: NIL,
+                right: NIL,
+            });
+            slot
+        }
+    }
+
+    // Put a slot on the free list and hand back its key
+    fn release(&mut self, slot: u32) -> K {
+        let node = &mut self.nodes[slot as usize];
+        node.generation = node.generation.wrapping_add(1);
+        node.parent = NIL;
+        node.left = NIL;
+        node.right = self.free_head;
+        self.free_head = slot;
+        node.key.take().unwrap()
+    }
+}
+
+impl<K: Ord> Default for ArenaBst<K> {
+    fn default() -> Self {
+        ArenaBst::new()
+    }
+}
+
+// In-order iterator returned by `ArenaBst::iter`, it follows tree_successor
+pub struct ArenaIter<'a, K> {
+    tree: &'a ArenaBst<K>,
+    next: Option<NodeId>,
+}
+
+impl<'a, K: Ord> Iterator for ArenaIter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next?;
+        self.next = self.tree.tree_successor(node);
+        Some(self.tree.key(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::test_util::TestRng;
+
+    // Check parent links and ordering of the whole tree
+    fn check(tree: &ArenaBst<i32>) {
+        let mut count = 0;
+        let mut stack: Vec<NodeId> = tree.root().into_iter().collect();
+        if let Some(root) = tree.root() {
+            assert_eq!(tree.parent(root), None);
+        }
+        while let Some(node) = stack.pop() {
+            count += 1;
+            for (child, is_left) in [(tree.left(node), true), (tree.right(node), false)] {
+                if let Some(child) = child {
+                    assert_eq!(tree.parent(child), Some(node));
+                    if is_left {
+                        assert!(tree.key(child) < tree.key(node));
+                    } else {
+                        assert!(tree.key(child) >= tree.key(node));
+                    }
+                    stack.push(child);
+                }
+            }
+        }
+        assert_eq!(count, tree.len());
+    }
+
+    #[test]
+    fn search_successor_and_delete() {
+        let mut tree = ArenaBst::new();
+        for key in [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9] {
+            tree.insert(key);
+        }
+        check(&tree);
+        let node = tree.tree_search(&13).unwrap();
+        assert_eq!(tree.tree_successor(node).map(|n| *tree.key(n)), Some(15));
+        assert_eq!(tree.tree_predecessor(node).map(|n| *tree.key(n)), Some(9));
+        assert_eq!(*tree.key(tree.minimum(tree.root().unwrap())), 2);
+        assert_eq!(*tree.key(tree.maximum(tree.root().unwrap())), 20);
+
+        // leaf, one child, successor is the right child, successor deeper, root
+        for key in [2, 7, 18, 6, 15] {
+            let node = tree.tree_search(&key).unwrap();
+            assert_eq!(tree.delete(node), Some(key));
+            assert!(!tree.contains(&key));
+            check(&tree);
+        }
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, 4, 9, 13, 17, 20]);
+
+        // freed slots are reused before the vector grows
+        let slots = tree.nodes.len();
+        let reused = tree.insert(8);
+        assert_eq!(*tree.key(reused), 8);
+        assert_eq!(tree.nodes.len(), slots);
+        assert!(!tree.remove(&100));
+    }
+
+    #[test]
+    fn stale_id_is_rejected() {
+        let mut tree = ArenaBst::new();
+        for key in [5, 3, 8, 1, 4] {
+            tree.insert(key);
+        }
+        tree.remove(&1);
+        let four = tree.tree_search(&4).unwrap();
+        assert_eq!(tree.delete(four), Some(4));
+        assert!(!tree.is_live(four));
+
+        // deleting again leaves the tree alone
+        let root = tree.root();
+        assert_eq!(tree.delete(four), None);
+        assert_eq!((tree.len(), tree.root()), (3, root));
+        check(&tree);
+
+        // the freed slot is reused, the old id still does not reach the new key
+        let six = tree.insert(6);
+        assert_eq!(six.index, four.index);
+        assert_eq!(tree.delete(four), None);
+        assert!(tree.contains(&6) && tree.is_live(six));
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![3, 5, 6, 8]);
+
+        tree.clear();
+        assert!(!tree.is_live(six) && tree.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "stale NodeId")]
+    fn stale_id_lookup_panics() {
+        let mut tree = ArenaBst::new();
+        let node = tree.insert(1);
+        tree.delete(node);
+        tree.key(node);
+    }
+
+    #[test]
+    fn random_keys_against_sorted_vec() {
+        let mut tree = ArenaBst::new();
+        let mut expected = Vec::new();
+        let mut rng = TestRng::new(11);
+        for _ in 0..2000 {
+            let key = rng.below(500) as i32;
+            if key % 3 == 0 && tree.remove(&key) {
+                let at = expected.iter().position(|&k| k == key).unwrap();
+                expected.remove(at);
+            } else {
+                tree.insert(key);
+                expected.push(key);
+            }
+        }
+        expected.sort();
+        check(&tree);
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), expected);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::tool::DotNodeStyle;
+
+// Height of an AVL node, a leaf has height 0 and an empty subtree counts as -1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct AvlHeight(pub i32);
+
+// AVL nodes are drawn like plain BST nodes
+impl DotNodeStyle for AvlHeight {}
+
+/**
+ * AVL nodes are regular `BstNode`s whose metadata is the node height,
+ * so `BstNode::tree_search`, `BstNode::tree_successor` and `tool::generate_dotfile_bst`
+ * work on them unchanged
+ */
+pub type AvlNodeLink<K = i32> = BstNodeLink<K, (), AvlHeight>;
+
+/**
+ * Self-balancing BST: after every insert and delete the heights on the path back to
+ * the root are refreshed and any node whose subtrees differ by more than one is rotated
+ * Keys are unique, inserting a key that is already present is rejected
+ */
+#[derive(Debug)]
+pub struct AvlTree<K = i32> {
+    root: Option<AvlNodeLink<K>>,
+    len: usize,
+}
+
+impl<K: Ord> AvlTree<K> {
+    pub fn new() -> Self {
+        AvlTree { root: None, len: 0 }
+    }
+
+    // Root of the tree, e.g. for tree_search or generate_dotfile_bst
+    pub fn root(&self) -> Option<&AvlNodeLink<K>> {
+        self.root.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Height of the whole tree, -1 when empty
+    pub fn height(&self) -> i32 {
+        height(&self.root)
+    }
+
+    /**
+     * Insert a key and rebalance from the new leaf upward
+     * Returns false if the key was already in the tree
+     */
+    pub fn insert(&mut self, key: K) -> bool {
+        match BstNode::insert_unique(&mut self.root, key, ()) {
+            Ok(new_node) => {
+                let parent = BstNode::upgrade_weak_to_strong(new_node.borrow().parent.clone());
+                rebalance_upward(&mut self.root, parent);
+                self.len += 1;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /**
+     * Remove a key with the regular `BstNode::delete` and rebalance from the lowest
+     * node whose subtree changed, returns false if the key is not in the tree
+     */
+    pub fn remove(&mut self, key: &K) -> bool {
+        let z = match self.search(key) {
+            Some(node) => node,
+            None => return false,
+        };
+        let fixup_start = BstNode::delete_fixup_start(&z);
+        BstNode::delete(&mut self.root, &z);
+        rebalance_upward(&mut self.root, fixup_start);
+        self.len -= 1;
+        true
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.search(key).is_some()
+    }
+
+    // Live node holding key, ready for tree_successor
+    pub fn search(&self, key: &K) -> Option<AvlNodeLink<K>> {
+        BstNode::tree_search(self.root.as_ref()?, key)
+    }
+
+    // All keys in order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = K>
+    where
+        K: Clone,
+    {
+        let nodes = self.root.as_ref().map(BstNode::in_order);
+        nodes.into_iter().flatten().map(|node| node.borrow().key.clone().unwrap())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+}
+
+impl<K: Ord> Default for AvlTree<K> {
+    fn default() -> Self {
+        AvlTree::new()
+    }
+}
+
+// Height of an optional subtree, -1 for an empty one
+fn height<K>(node: &Option<AvlNodeLink<K>>) -> i32 {
+    match node {
+        Some(exist) => exist.borrow().meta.0,
+        None => -1,
+    }
+}
+
+// Recompute the stored height from the children
+fn update_height<K>(node: &AvlNodeLink<K>) {
+    let new_height = 1 + height(&node.borrow().left).max(height(&node.borrow().right));
+    node.borrow_mut().meta = AvlHeight(new_height);
+}
+
+// Left height minus right height
+fn balance_factor<K>(node: &AvlNodeLink<K>) -> i32 {
+    height(&node.borrow().left) - height(&node.borrow().right)
+}
+
+/**
+ * Walk from node to the root through the parent links, refreshing heights and rotating
+ * every node that is out of balance
+ */
+fn rebalance_upward<K: Ord>(root: &mut Option<AvlNodeLink<K>>, node: Option<AvlNodeLink<K>>) {
+    let mut current = node;
+    while let Some(node) = current {
+        update_height(&node);
+        let balance = balance_factor(&node);
+        let subtree_root = if balance > 1 {
+            let left = node.borrow().left.clone().unwrap();
+            if balance_factor(&left) < 0 {
+                rotate_left(root, &left);
+            }
+            rotate_right(root, &node)
+        } else if balance < -1 {
+            let right = node.borrow().right.clone().unwrap();
+            if balance_factor(&right) > 0 {
+                rotate_right(root, &right);
+            }
+            rotate_left(root, &node)
+        } else {
+            node
+        };
+        current = BstNode::upgrade_weak_to_strong(subtree_root.borrow().parent.clone());
+    }
+}
+
+// BstNode::rotate_left followed by the height refresh of the two moved nodes
+fn rotate_left<K: Ord>(root: &mut Option<AvlNodeLink<K>>, x: &AvlNodeLink<K>) -> AvlNodeLink<K> {
+    let y = BstNode::rotate_left(root, x).unwrap();
+    update_height(x);
+    update_height(&y);
+    y
+}
+
+// BstNode::rotate_right followed by the height refresh of the two moved nodes
+fn rotate_right<K: Ord>(root: &mut Option<AvlNodeLink<K>>, x: &AvlNodeLink<K>) -> AvlNodeLink<K> {
+    let y = BstNode::rotate_right(root, x).unwrap();
+    update_height(x);
+    update_height(&y);
+    y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::test_util::{check_bst, TestRng};
+    use crate::tool::generate_dotfile_bst;
+
+    // Check ordering, links and sizes, then every stored height and balance factor
+    fn check(tree: &AvlTree) {
+        check_bst(tree.root());
+        if let Some(root) = tree.root() {
+            for node in BstNode::pre_order(root) {
+                let (left_height, right_height) = (height(&node.borrow().left), height(&node.borrow().right));
+                assert!((left_height - right_height).abs() <= 1, "unbalanced at {:?}", node.borrow().key);
+                assert_eq!(node.borrow().meta, AvlHeight(1 + left_height.max(right_height)));
+            }
+        }
+        assert_eq!(tree.iter().count(), tree.len());
+    }
+
+    #[test]
+    fn sorted_insert_stays_logarithmic() {
+        let mut tree = AvlTree::new();
+        for key in 0..1024 {
+            assert!(tree.insert(key));
+        }
+        check(&tree);
+        assert_eq!(tree.height(), 10);
+        assert!(!tree.insert(512));
+        assert_eq!(tree.len(), 1024);
+    }
+
+    #[test]
+    fn remove_rebalances_and_keeps_links() {
+        let mut tree = AvlTree::new();
+        let mut rng = TestRng::new(7);
+        let mut keys = Vec::new();
+        for _ in 0..300 {
+            let key = rng.below(500) as i32;
+            if tree.insert(key) {
+                keys.push(key);
+            }
+        }
+        check(&tree);
+        for (i, key) in keys.iter().enumerate() {
+            assert!(tree.remove(key));
+            assert!(!tree.contains(key));
+            if i % 10 == 0 {
+                check(&tree);
+            }
+        }
+        assert!(tree.is_empty());
+        assert!(tree.root().is_none());
+        assert!(!tree.remove(&1));
+    }
+
+    #[test]
+    fn search_and_successor_work_on_avl_nodes() {
+        let mut tree = AvlTree::new();
+        for key in [10, 20, 30, 40, 50] {
+            tree.insert(key);
+        }
+        let node = tree.search(&30).unwrap();
+        let successor = BstNode::tree_successor(&node).unwrap();
+        assert_eq!(successor.borrow().key, Some(40));
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![10, 20, 30, 40, 50]);
+
+        let dot_path = std::env::temp_dir().join("avl_graph_test.dot");
+        generate_dotfile_bst(tree.root().unwrap(), dot_path.to_str().unwrap());
+        let dot = std::fs::read_to_string(&dot_path).unwrap();
+        assert!(dot.contains("\t20--10;\n") && dot.contains("\t40--50;\n"));
+    }
+}
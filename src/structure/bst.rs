@@ -1,119 +1,418 @@
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
 use std::rc::{Rc, Weak};
 
-pub type BstNodeLink = Rc<RefCell<BstNode>>;
-pub type WeakBstNodeLink = Weak<RefCell<BstNode>>;
+use crate::structure::cursor::{Cursor, CursorMut};
+use crate::structure::traversal::{LevelOrderIter, PostOrderIter, PreOrderIter};
+
+/**
+ * Node links default to the integer set flavour (`BstNode<i32, ()>`) so existing
+ * callers keep writing `BstNodeLink`, while `BstMap` uses `BstNodeLink<K, V, M>`
+ * and the balanced variants put their per-node bookkeeping in `M`
+ */
+pub type BstNodeLink<K = i32, V = (), M = ()> = Rc<RefCell<BstNode<K, V, M>>>;
+pub type WeakBstNodeLink<K = i32, V = (), M = ()> = Weak<RefCell<BstNode<K, V, M>>>;
 
 // This package implements a wrapper for BST (Binary Search Tree)
 #[derive(Debug, Clone)]
-pub struct BstNode {
-    pub key: Option<i32>,
-    pub parent: Option<WeakBstNodeLink>,
-    pub left: Option<BstNodeLink>,
-    pub right: Option<BstNodeLink>,
+pub struct BstNode<K = i32, V = (), M = ()> {
+    pub key: Option<K>,
+    pub value: Option<V>,
+    // balancing metadata, e.g. the AVL height, unused (unit) in the plain BST
+    pub meta: M,
+    // multiplicity of the key, above 1 only in a `Bst` using `DuplicatePolicy::Count`
+    pub count: usize,
+    // number of keys in the subtree rooted here counting multiplicities, used by select and rank
+    pub size: usize,
+    pub parent: Option<WeakBstNodeLink<K, V, M>>,
+    pub left: Option<BstNodeLink<K, V, M>>,
+    pub right: Option<BstNodeLink<K, V, M>>,
 }
 
-impl BstNode {
-    // Create a new node with a specific key
-    fn new(key: i32) -> Self {
-        BstNode {
-            key: Some(key),
-            left: None,
-            right: None,
-            parent: None,
+/**
+ * Dropping a node drops its subtree without recursion: children whose last strong link
+ * is held here are detached onto a stack first, so each node is dropped childless and a
+ * degenerate tree of any depth cannot overflow the stack
+ * Subtrees still linked from elsewhere (a live handle) are left intact
+ */
+impl<K, V, M> Drop for BstNode<K, V, M> {
+    fn drop(&mut self) {
+        let mut stack: Vec<BstNodeLink<K, V, M>> = Vec::new();
+        stack.extend(self.left.take());
+        stack.extend(self.right.take());
+        while let Some(node) = stack.pop() {
+            if Rc::strong_count(&node) == 1 {
+                if let Ok(mut node_borrow) = node.try_borrow_mut() {
+                    stack.extend(node_borrow.left.take());
+                    stack.extend(node_borrow.right.take());
+                }
+            }
         }
     }
+}
 
-    // Public function to create a new node link with a value
-    pub fn new_bst_nodelink(value: i32) -> BstNodeLink {
-        let current_node = BstNode::new(value);
-        Rc::new(RefCell::new(current_node))
+// Outcome of `BstNode::insert_unique`: the new leaf, or the existing node and the rejected value
+pub type InsertResult<K, V, M> = Result<BstNodeLink<K, V, M>, (BstNodeLink<K, V, M>, V)>;
+
+// Outcome of `BstNode::find_slot`: the node holding the key, or where a node for it would hang
+#[derive(Debug)]
+pub enum Slot<K, V = (), M = ()> {
+    Occupied(BstNodeLink<K, V, M>),
+    // the would-be parent (None in an empty tree) and whether the new node goes to its left
+    Vacant(Option<BstNodeLink<K, V, M>>, bool),
+}
+
+// Outcome of `BstNode::split`: the roots of the smaller keys and of the remaining keys
+pub type SplitResult<K, V, M> = (Option<BstNodeLink<K, V, M>>, Option<BstNodeLink<K, V, M>>);
+
+// Outcome of `BstNode::try_join`: the joined root, or both input roots when their key ranges overlap
+pub type JoinResult<K, V, M> = Result<Option<BstNodeLink<K, V, M>>, SplitResult<K, V, M>>;
+
+// Which in-order neighbour takes the place of a deleted node that has two children
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteStrategy {
+    Successor,
+    Predecessor,
+}
+
+// How `Bst::insert` treats a key that is already in the tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    // the new key is dropped, the tree stays a set
+    Reject,
+    // the stored key is overwritten by the new, equal one, the tree stays a set
+    Replace,
+    // one node per key, a repeated key only bumps the node's count
+    Count,
+    // every insert adds a node, equal keys go to the right like in BstNode::insert
+    #[default]
+    Allow,
+}
+
+// What `BstNode::from_sorted` does with input that is not strictly increasing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortedInputPolicy {
+    // every key must be greater than the one before it
+    Strict,
+    // runs of equal keys are collapsed to their first key, a smaller key is still an error
+    Dedup,
+}
+
+// Why `BstNode::from_sorted` rejected its input, index is the position of the offending key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    Unsorted { index: usize },
+    Duplicate { index: usize },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Unsorted { index } => write!(f, "key at index {} is smaller than the one before it", index),
+            BuildError::Duplicate { index } => write!(f, "key at index {} repeats the one before it", index),
+        }
     }
+}
 
-    /**
-     * Get a copy of the node link
-     */
-    pub fn get_bst_nodelink_copy(&self) -> BstNodeLink {
-        Rc::new(RefCell::new(self.clone()))
+impl std::error::Error for BuildError {}
+
+// Key-only constructors, used when the tree is a plain set of keys
+impl<K: Ord> BstNode<K> {
+    // Create a new node with a specific key
+    fn new(key: K) -> Self {
+        BstNode::new_entry(key, ())
     }
 
-    // Convert a node to a weak reference
-    fn downgrade(node: &BstNodeLink) -> WeakBstNodeLink {
-        Rc::<RefCell<BstNode>>::downgrade(node)
+    // Public function to create a new node link with a value
+    pub fn new_bst_nodelink(value: K) -> BstNodeLink<K> {
+        BstNode::new_bst_nodelink_entry(value, ())
     }
 
     // Create a new node with a parent link
-    fn new_with_parent(parent: &BstNodeLink, value: i32) -> BstNodeLink {
+    fn new_with_parent(parent: &BstNodeLink<K>, value: K) -> BstNodeLink<K> {
         let mut current_node = BstNode::new(value);
         current_node.parent = Some(BstNode::downgrade(parent));
         Rc::new(RefCell::new(current_node))
     }
 
-    // Add a left child and set its parent to the current node
-    pub fn add_left_child(&mut self, current_node_link: &BstNodeLink, value: i32) {
+    /**
+     * Add a left child and set its parent to the current node
+     * Only this node's size is updated, the ancestors are usually still borrowed while
+     * building a tree by hand, so call `BstNode::recompute_sizes` on the root afterwards
+     */
+    pub fn add_left_child(&mut self, current_node_link: &BstNodeLink<K>, value: K) {
         let new_node = BstNode::new_with_parent(current_node_link, value);
+        self.size = self.size + 1 - BstNode::subtree_size(&self.left);
         self.left = Some(new_node);
     }
 
-    // Add a right child and set its parent to the current node
-    pub fn add_right_child(&mut self, current_node_link: &BstNodeLink, value: i32) {
+    // Add a right child and set its parent to the current node, see add_left_child about sizes
+    pub fn add_right_child(&mut self, current_node_link: &BstNodeLink<K>, value: K) {
         let new_node = BstNode::new_with_parent(current_node_link, value);
+        self.size = self.size + 1 - BstNode::subtree_size(&self.right);
         self.right = Some(new_node);
     }
 
-    // Search for a node with a matching value in the tree
-    pub fn tree_search(&self, value: &i32) -> Option<BstNodeLink> {
-        if let Some(key) = self.key {
-            if key == *value {
-                return Some(self.get_bst_nodelink_copy());
+    // Insert a new node with a key into the tree, equal keys go to the right
+    pub fn insert(root: &mut Option<BstNodeLink<K>>, key: K) {
+        let mut current = match root {
+            None => {
+                *root = Some(BstNode::new_bst_nodelink(key));
+                return;
             }
-            if *value < key && self.left.is_some() {
-                return self.left.as_ref().unwrap().borrow().tree_search(value);
-            } else if self.right.is_some() {
-                return self.right.as_ref().unwrap().borrow().tree_search(value);
+            Some(node) => node.clone(),
+        };
+        loop {
+            let mut node_borrow = current.borrow_mut();
+            // the new node always ends up somewhere below this one
+            node_borrow.size += 1;
+            let next = if key < *node_borrow.key.as_ref().unwrap() {
+                &mut node_borrow.left
+            } else {
+                &mut node_borrow.right
+            };
+            match next {
+                Some(child) => {
+                    let child = child.clone();
+                    drop(node_borrow); // Release borrow before going down
+                    current = child;
+                }
+                None => {
+                    let new_node = BstNode::new_bst_nodelink(key);
+                    new_node.borrow_mut().parent = Some(Rc::downgrade(&current));
+                    *next = Some(new_node);
+                    return;
+                }
             }
         }
-        None
     }
 
-    /** Recursively find the minimum value (always to the left in BST) */
-    pub fn minimum(&self) -> BstNodeLink {
-        if self.key.is_some() {
-            if let Some(left_node) = &self.left {
-                return left_node.borrow().minimum();
+    /**
+     * Build a height-balanced tree from sorted keys in O(n), without any comparisons
+     * beyond checking the order, returns None for empty input
+     * policy decides whether equal neighbours are an error or are dropped,
+     * keys going down are always rejected
+     */
+    pub fn from_sorted<I: IntoIterator<Item = K>>(keys: I, policy: SortedInputPolicy) -> Result<Option<BstNodeLink<K>>, BuildError> {
+        let mut nodes: Vec<BstNodeLink<K>> = Vec::new();
+        for (index, key) in keys.into_iter().enumerate() {
+            if let Some(last) = nodes.last() {
+                match key.cmp(last.borrow().key.as_ref().unwrap()) {
+                    Ordering::Less => return Err(BuildError::Unsorted { index }),
+                    Ordering::Equal => match policy {
+                        SortedInputPolicy::Strict => return Err(BuildError::Duplicate { index }),
+                        SortedInputPolicy::Dedup => continue,
+                    },
+                    Ordering::Greater => {}
+                }
             }
+            nodes.push(BstNode::new_bst_nodelink(key));
         }
-        self.get_bst_nodelink_copy()
+        Ok(BstNode::link_balanced(&nodes))
     }
+}
 
-    // Find the maximum value (always to the right in BST)
-    pub fn maximum(&self) -> BstNodeLink {
-        if self.key.is_some() {
-            if let Some(right_node) = &self.right {
-                return right_node.borrow().maximum();
+// Set operations between two key-only trees, the results are new balanced trees
+impl<K: Ord + Clone> BstNode<K> {
+    // Keys in either tree
+    pub fn union(a: &Option<BstNodeLink<K>>, b: &Option<BstNodeLink<K>>) -> Option<BstNodeLink<K>> {
+        BstNode::combine(a, b, |in_a, in_b| in_a || in_b)
+    }
+
+    // Keys in both trees
+    pub fn intersection(a: &Option<BstNodeLink<K>>, b: &Option<BstNodeLink<K>>) -> Option<BstNodeLink<K>> {
+        BstNode::combine(a, b, |in_a, in_b| in_a && in_b)
+    }
+
+    // Keys in a but not in b
+    pub fn difference(a: &Option<BstNodeLink<K>>, b: &Option<BstNodeLink<K>>) -> Option<BstNodeLink<K>> {
+        BstNode::combine(a, b, |in_a, in_b| in_a && !in_b)
+    }
+
+    // Keys in exactly one of the trees
+    pub fn symmetric_difference(a: &Option<BstNodeLink<K>>, b: &Option<BstNodeLink<K>>) -> Option<BstNodeLink<K>> {
+        BstNode::combine(a, b, |in_a, in_b| in_a != in_b)
+    }
+
+    // Every key of a is also in b
+    pub fn is_subset(a: &Option<BstNodeLink<K>>, b: &Option<BstNodeLink<K>>) -> bool {
+        let mut subset = true;
+        BstNode::merge_walk(a, b, |_, in_a, in_b| {
+            subset = !in_a || in_b;
+            subset
+        });
+        subset
+    }
+
+    // Every key of b is also in a
+    pub fn is_superset(a: &Option<BstNodeLink<K>>, b: &Option<BstNodeLink<K>>) -> bool {
+        BstNode::is_subset(b, a)
+    }
+
+    // No key is in both trees
+    pub fn is_disjoint(a: &Option<BstNodeLink<K>>, b: &Option<BstNodeLink<K>>) -> bool {
+        let mut disjoint = true;
+        BstNode::merge_walk(a, b, |_, in_a, in_b| {
+            disjoint = !(in_a && in_b);
+            disjoint
+        });
+        disjoint
+    }
+
+    // Keep the keys for which keep(in_a, in_b) holds and build a balanced tree from them
+    fn combine(a: &Option<BstNodeLink<K>>, b: &Option<BstNodeLink<K>>, keep: impl Fn(bool, bool) -> bool) -> Option<BstNodeLink<K>> {
+        let mut nodes = Vec::new();
+        BstNode::merge_walk(a, b, |key, in_a, in_b| {
+            if keep(in_a, in_b) {
+                nodes.push(BstNode::new_bst_nodelink(key.clone()));
+            }
+            true
+        });
+        BstNode::link_balanced(&nodes)
+    }
+
+    /**
+     * Walk both trees in order at the same time, like the merge step of merge sort,
+     * and call visit once per distinct key with whether a and b hold it
+     * Stops early when visit returns false. O(n + m) since in-order steps are amortized O(1)
+     */
+    fn merge_walk(a: &Option<BstNodeLink<K>>, b: &Option<BstNodeLink<K>>, mut visit: impl FnMut(&K, bool, bool) -> bool) {
+        let mut a_keys = BstNode::distinct_keys(a).peekable();
+        let mut b_keys = BstNode::distinct_keys(b).peekable();
+        loop {
+            let (key, in_a, in_b) = match (a_keys.peek(), b_keys.peek()) {
+                (None, None) => return,
+                (Some(_), None) => (a_keys.next().unwrap(), true, false),
+                (None, Some(_)) => (b_keys.next().unwrap(), false, true),
+                (Some(a_key), Some(b_key)) => match a_key.cmp(b_key) {
+                    Ordering::Less => (a_keys.next().unwrap(), true, false),
+                    Ordering::Greater => (b_keys.next().unwrap(), false, true),
+                    Ordering::Equal => {
+                        b_keys.next();
+                        (a_keys.next().unwrap(), true, true)
+                    }
+                },
+            };
+            if !visit(&key, in_a, in_b) {
+                return;
+            }
+        }
+    }
+
+    // Keys in order with duplicates skipped, a plain BST may hold the same key twice
+    fn distinct_keys(root: &Option<BstNodeLink<K>>) -> impl Iterator<Item = K> {
+        let mut last: Option<K> = None;
+        let nodes = root.as_ref().map(BstNode::in_order);
+        nodes.into_iter().flatten().map(|node| node.borrow().key.clone().unwrap()).filter(move |key| {
+            if last.as_ref() == Some(key) {
+                return false;
             }
+            last = Some(key.clone());
+            true
+        })
+    }
+}
+
+impl<K: Ord, V, M> BstNode<K, V, M> {
+    /**
+     * Search for a node with a matching value in the subtree rooted at node
+     * The returned link is the node inside the tree (not a copy), so it can be passed
+     * straight to `delete` or `tree_successor`
+     */
+    pub fn tree_search(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        let mut current = node.clone();
+        loop {
+            let ordering = value.cmp(current.borrow().key.as_ref()?);
+            let next = match ordering {
+                Ordering::Equal => return Some(current),
+                Ordering::Less => current.borrow().left.clone(),
+                Ordering::Greater => current.borrow().right.clone(),
+            };
+            current = next?;
         }
-        self.get_bst_nodelink_copy()
     }
 
     /**
-     * Return the root node of the tree, or return self if it has no parent
+     * Floor query: the node with the largest key that is <= value
+     * Like tree_search the returned link is the live node, ready for tree_successor
+     */
+    pub fn floor(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::closest_on_path(node, |key| key <= value, true)
+    }
+
+    // Ceiling query: the node with the smallest key that is >= value
+    pub fn ceiling(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::closest_on_path(node, |key| key >= value, false)
+    }
+
+    // Strict lower bound: the node with the largest key that is < value
+    pub fn lower_bound(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::closest_on_path(node, |key| key < value, true)
+    }
+
+    // Strict upper bound: the node with the smallest key that is > value
+    pub fn upper_bound(node: &BstNodeLink<K, V, M>, value: &K) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::closest_on_path(node, |key| key > value, false)
+    }
+
+    /**
+     * Shared descent for the bound queries: every node whose key satisfies `accept` is a
+     * candidate, and the search continues towards keys closer to the query value
+     * (right for floor style queries, left for ceiling style queries)
      */
-    pub fn get_root(node: &BstNodeLink) -> BstNodeLink {
-        let parent = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
-        if parent.is_none() {
-            return node.clone();
+    fn closest_on_path<F>(node: &BstNodeLink<K, V, M>, accept: F, accepted_goes_right: bool) -> Option<BstNodeLink<K, V, M>>
+    where
+        F: Fn(&K) -> bool,
+    {
+        let mut candidate = None;
+        let mut current = Some(node.clone());
+        while let Some(exist) = current {
+            let accepted = accept(exist.borrow().key.as_ref()?);
+            current = if accepted == accepted_goes_right {
+                exist.borrow().right.clone()
+            } else {
+                exist.borrow().left.clone()
+            };
+            if accepted {
+                candidate = Some(exist);
+            }
+        }
+        candidate
+    }
+
+    /** Find the minimum value (always to the left in BST) */
+    pub fn minimum(node: &BstNodeLink<K, V, M>) -> BstNodeLink<K, V, M> {
+        let mut current = node.clone();
+        loop {
+            let left_node = current.borrow().left.clone();
+            match left_node {
+                Some(left_node) => current = left_node,
+                None => return current,
+            }
+        }
+    }
+
+    // Find the maximum value (always to the right in BST)
+    pub fn maximum(node: &BstNodeLink<K, V, M>) -> BstNodeLink<K, V, M> {
+        let mut current = node.clone();
+        loop {
+            let right_node = current.borrow().right.clone();
+            match right_node {
+                Some(right_node) => current = right_node,
+                None => return current,
+            }
         }
-        return BstNode::get_root(&parent.unwrap());
     }
 
     /**
      * Find the successor of a node according to the BST rules.
      * Returns None if the node is the highest key in the tree.
      */
-    pub fn tree_successor(x_node: &BstNodeLink) -> Option<BstNodeLink> {
+    pub fn tree_successor(x_node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
         if let Some(right_node) = &x_node.borrow().right {
-            return Some(right_node.borrow().minimum());
+            return Some(BstNode::minimum(right_node));
         }
         
         let mut x_node = x_node.clone();  
@@ -133,42 +432,94 @@ impl BstNode {
         None
     }
 
-    // Insert a new node with a key into the tree
-    pub fn insert(root: &mut Option<BstNodeLink>, key: i32) {
-        match root {
-            None => {
-                *root = Some(BstNode::new_bst_nodelink(key));
-            }
-            Some(node) => {
-                let mut node_borrow = node.borrow_mut();
-                if key < node_borrow.key.unwrap() {
-                    if node_borrow.left.is_none() {
-                        let new_node = BstNode::new_bst_nodelink(key);
-                        new_node.borrow_mut().parent = Some(Rc::downgrade(node));
-                        node_borrow.left = Some(new_node);
-                    } else {
-                        drop(node_borrow); // Release borrow before recursion
-                        BstNode::insert(&mut node.borrow_mut().left, key);
-                    }
-                } else {
-                    if node_borrow.right.is_none() {
-                        let new_node = BstNode::new_bst_nodelink(key);
-                        new_node.borrow_mut().parent = Some(Rc::downgrade(node));
-                        node_borrow.right = Some(new_node);
-                    } else {
-                        drop(node_borrow);
-                        BstNode::insert(&mut node.borrow_mut().right, key);
-                    }
+    /**
+     * Find the predecessor of a node, the mirror of tree_successor.
+     * Returns None if the node is the lowest key in the tree.
+     */
+    pub fn tree_predecessor(x_node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
+        if let Some(left_node) = &x_node.borrow().left {
+            return Some(BstNode::maximum(left_node));
+        }
+
+        let mut x_node = x_node.clone();
+        let mut y_node_opt = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone());
+
+        while let Some(exist) = y_node_opt {
+            if let Some(ref right_child) = exist.borrow().right {
+                if BstNode::is_node_match(right_child, &x_node) {
+                    return Some(exist.clone());
                 }
             }
+
+            x_node = exist.clone();
+            y_node_opt = BstNode::upgrade_weak_to_strong(exist.borrow().parent.clone());
+        }
+
+        None
+    }
+
+    /**
+     * A simpler version of tree_successor that checks if the node is nil
+     */
+    #[allow(dead_code, clippy::needless_return, clippy::bool_comparison, clippy::needless_borrow)]
+    pub fn tree_successor_simpler(x_node: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
+        let mut x_node = x_node;
+        let right_node = &x_node.borrow().right.clone();
+        if BstNode::is_nil(right_node) != true {
+            return Some(BstNode::minimum(right_node.as_ref().unwrap()));
+        }
+
+        let mut y_node = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone());
+        let y_node_right = &y_node.clone().unwrap().borrow().right.clone();
+        let mut y_node2: BstNodeLink<K, V, M>;
+
+        while BstNode::is_nil(&y_node) && BstNode::is_node_match_option(Some(x_node.clone()), y_node_right.clone()) {
+            y_node2 = y_node.clone().unwrap();
+            x_node = &y_node2;
+            let y_parent = y_node.clone().unwrap().borrow().parent.clone().unwrap();
+            y_node = BstNode::upgrade_weak_to_strong(Some(y_parent));
+        }
+
+        if BstNode::is_node_match_option(y_node.clone(), Some(BstNode::get_root(&x_node))) {
+            return None;
+        }
+
+        return Some(y_node.clone().unwrap());
+    }
+
+    // Convert a node to a weak reference
+    fn downgrade(node: &BstNodeLink<K, V, M>) -> WeakBstNodeLink<K, V, M> {
+        Rc::downgrade(node)
+    }
+
+    /**
+     * Return the root node of the tree, or return self if it has no parent
+     */
+    pub fn get_root(node: &BstNodeLink<K, V, M>) -> BstNodeLink<K, V, M> {
+        let mut current = node.clone();
+        loop {
+            let parent = BstNode::upgrade_weak_to_strong(current.borrow().parent.clone());
+            match parent {
+                Some(parent) => current = parent,
+                None => return current,
+            }
         }
     }
 
-    // Replace a node in the tree (used for deletion)
-    pub fn transplant(root: &mut Option<BstNodeLink>, u: &BstNodeLink, v: Option<BstNodeLink>) {
+    /**
+     * Replace a node in the tree (used for deletion)
+     * The sizes from u's parent up to the root are refreshed, so v's own size must be right
+     */
+    pub fn transplant(root: &mut Option<BstNodeLink<K, V, M>>, u: &BstNodeLink<K, V, M>, v: Option<BstNodeLink<K, V, M>>) {
+        BstNode::replace_in_parent(root, u, v);
+        BstNode::update_size_upward(BstNode::upgrade_weak_to_strong(u.borrow().parent.clone()));
+    }
+
+    // The pointer part of transplant: hang v where u hangs, without touching any size
+    fn replace_in_parent(root: &mut Option<BstNodeLink<K, V, M>>, u: &BstNodeLink<K, V, M>, v: Option<BstNodeLink<K, V, M>>) {
         if let Some(parent_weak) = &u.borrow().parent {
             if let Some(parent) = parent_weak.upgrade() {
-                if Rc::ptr_eq(u, parent.borrow().left.as_ref().unwrap_or(u)) {
+                if parent.borrow().left.as_ref().is_some_and(|left| Rc::ptr_eq(u, left)) {
                     parent.borrow_mut().left = v.clone();
                 } else {
                     parent.borrow_mut().right = v.clone();
@@ -183,110 +534,1602 @@ impl BstNode {
         }
     }
 
-    // Delete a node from the tree
-    pub fn delete(root: &mut Option<BstNodeLink>, z: &BstNodeLink) {
+    // Delete a node from the tree, a node with two children is replaced by its successor
+    pub fn delete(root: &mut Option<BstNodeLink<K, V, M>>, z: &BstNodeLink<K, V, M>) {
+        BstNode::delete_with(root, z, DeleteStrategy::Successor);
+    }
+
+    /**
+     * Delete a node from the tree, the strategy decides whether a node with two children
+     * is replaced by its in-order successor or its in-order predecessor
+     */
+    pub fn delete_with(root: &mut Option<BstNodeLink<K, V, M>>, z: &BstNodeLink<K, V, M>, strategy: DeleteStrategy) {
         let z_left = z.borrow().left.clone();
         let z_right = z.borrow().right.clone();
 
-        if z_left.is_none() {
-            BstNode::transplant(root, z, z_right);
-        } else if z_right.is_none() {
-            BstNode::transplant(root, z, z_left);
-        } else {
-            let mut y = z_right.clone();
-            while let Some(ref y_node) = y {
-                if y_node.borrow().left.is_some() {
-                    let left_child = y_node.borrow().left.clone();
-                    y = left_child;
-                } else {
-                    break;
+        match (z_left, z_right) {
+            (None, z_right) => BstNode::transplant(root, z, z_right),
+            (z_left, None) => BstNode::transplant(root, z, z_left),
+            (Some(z_left), Some(z_right)) if strategy == DeleteStrategy::Predecessor => {
+                // mirror of the successor case: y is the maximum of the left subtree
+                let y_node = BstNode::maximum(&z_left);
+                if !Rc::ptr_eq(&y_node, &z_left) {
+                    let y_left = y_node.borrow().left.clone();
+                    BstNode::transplant(root, &y_node, y_left);
+                    z_left.borrow_mut().parent = Some(Rc::downgrade(&y_node));
+                    y_node.borrow_mut().left = Some(z_left);
                 }
-            }
 
-            if let Some(y_node) = y.clone() {
-                if !Rc::ptr_eq(&y_node, &z_right.as_ref().unwrap()) {
+                // y gets both subtrees before it is hung in place, so its size is final for transplant
+                z_right.borrow_mut().parent = Some(Rc::downgrade(&y_node));
+                y_node.borrow_mut().right = Some(z_right);
+                BstNode::update_size(&y_node);
+                BstNode::transplant(root, z, Some(y_node));
+            }
+            (Some(z_left), Some(z_right)) => {
+                let y_node = BstNode::minimum(&z_right);
+                if !Rc::ptr_eq(&y_node, &z_right) {
                     let y_right = y_node.borrow().right.clone();
                     BstNode::transplant(root, &y_node, y_right);
-                    y_node.borrow_mut().right = z_right.clone();
-                    if let Some(ref right_child) = y_node.borrow().right {
-                        right_child.borrow_mut().parent = Some(Rc::downgrade(&y_node));
-                    }
+                    z_right.borrow_mut().parent = Some(Rc::downgrade(&y_node));
+                    y_node.borrow_mut().right = Some(z_right);
                 }
 
-                BstNode::transplant(root, z, y.clone());
-                y_node.borrow_mut().left = z_left.clone();
+                z_left.borrow_mut().parent = Some(Rc::downgrade(&y_node));
+                y_node.borrow_mut().left = Some(z_left);
+                BstNode::update_size(&y_node);
+                BstNode::transplant(root, z, Some(y_node));
             }
         }
     }
 
     /**
-     * A simpler version of tree_successor that checks if the node is nil
+     * The lowest node whose subtree changes when `delete` removes z: z's parent when z has
+     * at most one child, otherwise the successor y itself (y is z's right child) or y's parent.
+     * Augmented variants refresh their per-node data from here up to the root after the delete
      */
-    #[allow(dead_code)]
-    pub fn tree_successor_simpler(x_node: &BstNodeLink) -> Option<BstNodeLink> {
-        let mut x_node = x_node;
-        let right_node = &x_node.borrow().right.clone();
-        if BstNode::is_nil(right_node) != true {
-            return Some(right_node.clone().unwrap().borrow().minimum());
+    pub fn delete_fixup_start(z: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
+        let z_right = z.borrow().right.clone();
+        match z_right {
+            Some(right) if z.borrow().left.is_some() => {
+                let y = BstNode::minimum(&right);
+                if Rc::ptr_eq(&y, &right) {
+                    Some(y)
+                } else {
+                    BstNode::upgrade_weak_to_strong(y.borrow().parent.clone())
+                }
+            }
+            _ => BstNode::upgrade_weak_to_strong(z.borrow().parent.clone()),
         }
+    }
 
-        let mut y_node = BstNode::upgrade_weak_to_strong(x_node.borrow().parent.clone());
-        let y_node_right = &y_node.clone().unwrap().borrow().right.clone();
-        let mut y_node2: Rc<RefCell<BstNode>>;
+    /**
+     * Iterate over the live nodes whose keys fall inside range, in key order
+     * Only the path to the lower bound and the nodes inside the range are visited
+     */
+    pub fn range<R: RangeBounds<K>>(node: &BstNodeLink<K, V, M>, range: R) -> BstRange<K, V, M, R> {
+        let next = match range.start_bound() {
+            Bound::Included(start) => BstNode::ceiling(node, start),
+            Bound::Excluded(start) => BstNode::upper_bound(node, start),
+            Bound::Unbounded => Some(BstNode::minimum(node)),
+        };
+        BstRange { next, range }
+    }
 
-        while BstNode::is_nil(&y_node) && BstNode::is_node_match_option(Some(x_node.clone()), y_node_right.clone()) {
-            y_node2 = y_node.clone().unwrap();
-            x_node = &y_node2;
-            let y_parent = y_node.clone().unwrap().borrow().parent.clone().unwrap();
-            y_node = BstNode::upgrade_weak_to_strong(Some(y_parent));
+    // Number of keys inside range, counting multiplicities
+    pub fn range_count<R: RangeBounds<K>>(node: &BstNodeLink<K, V, M>, range: R) -> usize {
+        BstNode::range(node, range).map(|node| node.borrow().count).sum()
+    }
+
+    /**
+     * Delete every node whose key falls inside range and return how many keys were removed
+     * The nodes are collected first, `delete` moves nodes rather than keys so the
+     * collected links stay valid while the tree is being reshaped
+     */
+    pub fn range_delete<R: RangeBounds<K>>(root: &mut Option<BstNodeLink<K, V, M>>, range: R) -> usize {
+        let doomed: Vec<BstNodeLink<K, V, M>> = match root {
+            Some(node) => BstNode::range(node, range).collect(),
+            None => return 0,
+        };
+        let mut removed = 0;
+        for node in doomed.iter() {
+            removed += node.borrow().count;
+            BstNode::delete(root, node);
         }
+        removed
+    }
 
-        if BstNode::is_node_match_option(y_node.clone(), Some(BstNode::get_root(&x_node))) {
-            return None;
+    /**
+     * Rotate x down to the left so its right child y takes its place:
+     * y's left subtree becomes x's right subtree and x becomes y's left child.
+     * The parent's child pointer (or root, when x was the root) and the parent links
+     * of x, y and the moved subtree are all updated.
+     * Returns y, the new root of the rotated subtree, or None if x has no right child
+     */
+    pub fn rotate_left(root: &mut Option<BstNodeLink<K, V, M>>, x: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
+        let y = x.borrow().right.clone()?;
+        let y_left = y.borrow_mut().left.take();
+        if let Some(ref child) = y_left {
+            child.borrow_mut().parent = Some(Rc::downgrade(x));
         }
+        x.borrow_mut().right = y_left;
+        BstNode::replace_in_parent(root, x, Some(y.clone()));
+        y.borrow_mut().left = Some(x.clone());
+        x.borrow_mut().parent = Some(Rc::downgrade(&y));
+        // y now spans exactly the nodes x used to span, the ancestors are unchanged
+        let x_size = x.borrow().size;
+        y.borrow_mut().size = x_size;
+        BstNode::update_size(x);
+        Some(y)
+    }
 
-        return Some(y_node.clone().unwrap());
+    // Mirror of rotate_left: x's left child takes its place, None if x has no left child
+    pub fn rotate_right(root: &mut Option<BstNodeLink<K, V, M>>, x: &BstNodeLink<K, V, M>) -> Option<BstNodeLink<K, V, M>> {
+        let y = x.borrow().left.clone()?;
+        let y_right = y.borrow_mut().right.take();
+        if let Some(ref child) = y_right {
+            child.borrow_mut().parent = Some(Rc::downgrade(x));
+        }
+        x.borrow_mut().left = y_right;
+        BstNode::replace_in_parent(root, x, Some(y.clone()));
+        y.borrow_mut().right = Some(x.clone());
+        x.borrow_mut().parent = Some(Rc::downgrade(&y));
+        // y now spans exactly the nodes x used to span, the ancestors are unchanged
+        let x_size = x.borrow().size;
+        y.borrow_mut().size = x_size;
+        BstNode::update_size(x);
+        Some(y)
     }
 
-    // Check if a node is "nil" (no parent, no children, and no key)
-    fn is_nil(node: &Option<BstNodeLink>) -> bool {
-        match node {
-            None => true,
-            Some(x) => {
-                if x.borrow().parent.is_none() || x.borrow().left.is_none() || x.borrow().right.is_none() {
-                    return true;
+    /**
+     * Day-Stout-Warren rebalance of the whole tree, in place and in O(n)
+     * Right rotations first straighten the tree into a vine (every node only has a right
+     * child), then rounds of left rotations along the vine fold it into a balanced tree.
+     * Nodes are never copied, rotate_left and rotate_right keep parents and sizes valid
+     */
+    pub fn rebalance(root: &mut Option<BstNodeLink<K, V, M>>) {
+        // tree to vine, counting the nodes as they settle on it (size counts every copy of a key)
+        let mut node_count = 0;
+        let mut current = root.clone();
+        while let Some(node) = current {
+            current = if node.borrow().left.is_some() {
+                BstNode::rotate_right(root, &node)
+            } else {
+                node_count += 1;
+                node.borrow().right.clone()
+            };
+        }
+
+        // vine to tree: first leave a perfect tree worth of nodes on the vine, then halve
+        let mut perfect = 0;
+        while perfect * 2 < node_count {
+            perfect = perfect * 2 + 1;
+        }
+        BstNode::compress(root, node_count - perfect);
+        while perfect > 1 {
+            perfect /= 2;
+            BstNode::compress(root, perfect);
+        }
+    }
+
+    // One DSW round: rotate every other node of the right spine left, count times
+    fn compress(root: &mut Option<BstNodeLink<K, V, M>>, count: usize) {
+        let mut current = root.clone();
+        for _ in 0..count {
+            let node = match current {
+                Some(node) => node,
+                None => return,
+            };
+            current = match BstNode::rotate_left(root, &node) {
+                Some(new_top) => new_top.borrow().right.clone(),
+                None => return,
+            };
+        }
+    }
+
+    /**
+     * Split the tree rooted at root into the keys smaller than key and the keys greater
+     * than or equal to key, returned as (smaller, rest)
+     * Only the search path for key is cut and relinked, every node is reused and the
+     * sizes on that path are refreshed bottom-up, so this is O(h)
+     */
+    pub fn split(root: Option<BstNodeLink<K, V, M>>, key: &K) -> SplitResult<K, V, M> {
+        let mut smaller: Option<BstNodeLink<K, V, M>> = None;
+        let mut rest: Option<BstNodeLink<K, V, M>> = None;
+        // last node put in each tree, the next piece hangs off its right (smaller) or left (rest)
+        let mut smaller_tail: Option<BstNodeLink<K, V, M>> = None;
+        let mut rest_tail: Option<BstNodeLink<K, V, M>> = None;
+        let mut path = Vec::new();
+
+        let mut current = root;
+        while let Some(node) = current {
+            path.push(node.clone());
+            let goes_smaller = node.borrow().key.as_ref().unwrap() < key;
+            if goes_smaller {
+                current = node.borrow_mut().right.take();
+                match &smaller_tail {
+                    Some(tail) => {
+                        node.borrow_mut().parent = Some(Rc::downgrade(tail));
+                        tail.borrow_mut().right = Some(node.clone());
+                    }
+                    None => {
+                        node.borrow_mut().parent = None;
+                        smaller = Some(node.clone());
+                    }
                 }
-                return false;
+                smaller_tail = Some(node);
+            } else {
+                current = node.borrow_mut().left.take();
+                match &rest_tail {
+                    Some(tail) => {
+                        node.borrow_mut().parent = Some(Rc::downgrade(tail));
+                        tail.borrow_mut().left = Some(node.clone());
+                    }
+                    None => {
+                        node.borrow_mut().parent = None;
+                        rest = Some(node.clone());
+                    }
+                }
+                rest_tail = Some(node);
             }
         }
+        for node in path.iter().rev() {
+            BstNode::update_size(node);
+        }
+        (smaller, rest)
     }
 
-    // Helper function to check if two nodes are equal by comparing their keys
-    fn is_node_match_option(node1: Option<BstNodeLink>, node2: Option<BstNodeLink>) -> bool {
-        if node1.is_none() && node2.is_none() {
-            return true;
+    /**
+     * Join two trees where every key of left is smaller than or equal to every key of right
+     * The maximum of left is unlinked and becomes the new root with both trees below it,
+     * no other node moves. Panics if the key ranges overlap, see try_join
+     */
+    pub fn join(left: Option<BstNodeLink<K, V, M>>, right: Option<BstNodeLink<K, V, M>>) -> Option<BstNodeLink<K, V, M>> {
+        BstNode::try_join(left, right)
+            .unwrap_or_else(|_| panic!("join needs every key of the left tree to be at most every key of the right tree"))
+    }
+
+    // Like join, but overlapping key ranges hand both trees back untouched instead of panicking
+    pub fn try_join(left: Option<BstNodeLink<K, V, M>>, right: Option<BstNodeLink<K, V, M>>) -> JoinResult<K, V, M> {
+        let mut left = left;
+        let (left_root, right_root) = match (&left, &right) {
+            (None, _) => return Ok(right),
+            (_, None) => return Ok(left),
+            (Some(left_root), Some(right_root)) => (left_root.clone(), right_root.clone()),
+        };
+        let new_root = BstNode::maximum(&left_root);
+        let right_min = BstNode::minimum(&right_root);
+        if new_root.borrow().key > right_min.borrow().key {
+            return Err((left, right));
         }
-        if let Some(node1v) = node1 {
-            return node2.is_some_and(|x: BstNodeLink| x.borrow().key == node1v.borrow().key);
+
+        let new_root_left = new_root.borrow().left.clone();
+        BstNode::transplant(&mut left, &new_root, new_root_left);
+        for child in [&left, &right].into_iter().flatten() {
+            child.borrow_mut().parent = Some(Rc::downgrade(&new_root));
         }
-        return false;
+        let mut new_root_borrow = new_root.borrow_mut();
+        new_root_borrow.parent = None;
+        new_root_borrow.left = left;
+        new_root_borrow.right = right;
+        drop(new_root_borrow);
+        BstNode::update_size(&new_root);
+        Ok(Some(new_root))
     }
 
-    // Check if two nodes are equal
-    fn is_node_match(anode: &BstNodeLink, bnode: &BstNodeLink) -> bool {
-        if anode.borrow().key == bnode.borrow().key {
-            return true;
+    // Number of keys in an optional subtree
+    pub fn subtree_size(node: &Option<BstNodeLink<K, V, M>>) -> usize {
+        match node {
+            Some(exist) => exist.borrow().size,
+            None => 0,
+        }
+    }
+
+    // Recompute the size of node from its own count and its children
+    fn update_size(node: &BstNodeLink<K, V, M>) {
+        let size = node.borrow().count + BstNode::subtree_size(&node.borrow().left) + BstNode::subtree_size(&node.borrow().right);
+        node.borrow_mut().size = size;
+    }
+
+    // Recompute the sizes from node up to the root
+    pub(crate) fn update_size_upward(node: Option<BstNodeLink<K, V, M>>) {
+        let mut current = node;
+        while let Some(exist) = current {
+            BstNode::update_size(&exist);
+            current = BstNode::upgrade_weak_to_strong(exist.borrow().parent.clone());
+        }
+    }
+
+    // Recompute every size in the subtree, children before parents, e.g. after add_left_child
+    pub fn recompute_sizes(node: &BstNodeLink<K, V, M>) {
+        for exist in BstNode::post_order(node) {
+            BstNode::update_size(&exist);
         }
-        return false;
     }
 
     /**
-     * Upgrade a weak reference to a strong reference
+     * Single descent for key: the live node holding it, or the empty child slot where
+     * a node for it would be attached, ready for `attach`
      */
-     fn upgrade_weak_to_strong(node: Option<WeakBstNodeLink>) -> Option<BstNodeLink> {
-        match node {
-            None => None,
-            Some(x) => x.upgrade(),  
+    pub fn find_slot(root: &Option<BstNodeLink<K, V, M>>, key: &K) -> Slot<K, V, M> {
+        let mut current = match root {
+            Some(node) => node.clone(),
+            None => return Slot::Vacant(None, false),
+        };
+        loop {
+            let ordering = key.cmp(current.borrow().key.as_ref().unwrap());
+            let next = match ordering {
+                Ordering::Equal => return Slot::Occupied(current),
+                Ordering::Less => current.borrow().left.clone(),
+                Ordering::Greater => current.borrow().right.clone(),
+            };
+            current = match next {
+                Some(child) => child,
+                None => return Slot::Vacant(Some(current), ordering == Ordering::Less),
+            };
+        }
+    }
+
+    // Node holding a key, its value and a metadata value that has no default, e.g. an interval's max end
+    fn new_entry_with_meta(key: K, value: V, meta: M) -> Self {
+        BstNode {
+            key: Some(key),
+            value: Some(value),
+            meta,
+            count: 1,
+            size: 1,
+            left: None,
+            right: None,
+            parent: None,
+        }
+    }
+
+    // Like `attach`, the new leaf starts with the given metadata
+    pub fn attach_with_meta(
+        root: &mut Option<BstNodeLink<K, V, M>>,
+        parent: Option<BstNodeLink<K, V, M>>,
+        is_left: bool,
+        key: K,
+        value: V,
+        meta: M,
+    ) -> BstNodeLink<K, V, M> {
+        let new_node = Rc::new(RefCell::new(BstNode::new_entry_with_meta(key, value, meta)));
+        match parent {
+            None => *root = Some(new_node.clone()),
+            Some(parent) => {
+                new_node.borrow_mut().parent = Some(BstNode::downgrade(&parent));
+                if is_left {
+                    parent.borrow_mut().left = Some(new_node.clone());
+                } else {
+                    parent.borrow_mut().right = Some(new_node.clone());
+                }
+                BstNode::update_size_upward(Some(parent));
+            }
+        }
+        new_node
+    }
+
+    /**
+     * Link nodes, given in key order, into a perfectly balanced subtree and return its root
+     * Every child and parent link and every size inside is overwritten, the parent link
+     * of the returned root is left alone for the caller to set
+     */
+    pub fn link_balanced(nodes: &[BstNodeLink<K, V, M>]) -> Option<BstNodeLink<K, V, M>> {
+        if nodes.is_empty() {
+            return None;
+        }
+        let mid = nodes.len() / 2;
+        let middle = nodes[mid].clone();
+        let left = BstNode::link_balanced(&nodes[..mid]);
+        let right = BstNode::link_balanced(&nodes[mid + 1..]);
+        for child in [&left, &right].into_iter().flatten() {
+            child.borrow_mut().parent = Some(BstNode::downgrade(&middle));
+        }
+        let mut middle_borrow = middle.borrow_mut();
+        middle_borrow.left = left;
+        middle_borrow.right = right;
+        drop(middle_borrow);
+        BstNode::update_size(&middle);
+        Some(middle)
+    }
+
+    /**
+     * Order statistic: the node holding the k-th smallest key, counting from 0
+     * Uses the subtree sizes, so only one path from node downward is visited.
+     * A node with a count above 1 holds that many consecutive positions
+     */
+    pub fn select(node: &BstNodeLink<K, V, M>, k: usize) -> Option<BstNodeLink<K, V, M>> {
+        let mut k = k;
+        let mut current = node.clone();
+        loop {
+            let left_size = BstNode::subtree_size(&current.borrow().left);
+            let count = current.borrow().count;
+            let next = if k < left_size {
+                current.borrow().left.clone()
+            } else if k < left_size + count {
+                return Some(current);
+            } else {
+                k -= left_size + count;
+                current.borrow().right.clone()
+            };
+            current = next?;
+        }
+    }
+
+    // Number of keys in the subtree that are strictly smaller than value
+    pub fn rank(node: &BstNodeLink<K, V, M>, value: &K) -> usize {
+        let mut rank = 0;
+        let mut current = Some(node.clone());
+        while let Some(exist) = current {
+            current = if value <= exist.borrow().key.as_ref().unwrap() {
+                exist.borrow().left.clone()
+            } else {
+                rank += BstNode::subtree_size(&exist.borrow().left) + exist.borrow().count;
+                exist.borrow().right.clone()
+            };
+        }
+        rank
+    }
+
+    // In-order iterator, walks the tree with tree_successor and tree_predecessor
+    pub fn in_order(node: &BstNodeLink<K, V, M>) -> BstInOrder<K, V, M> {
+        BstInOrder {
+            front: Some(BstNode::minimum(node)),
+            back: Some(BstNode::maximum(node)),
+        }
+    }
+
+    // Pre-order iterator over the subtree rooted at node
+    pub fn pre_order(node: &BstNodeLink<K, V, M>) -> PreOrderIter<BstNodeLink<K, V, M>> {
+        PreOrderIter::new(Some(node.clone()))
+    }
+
+    // Post-order iterator over the subtree rooted at node
+    pub fn post_order(node: &BstNodeLink<K, V, M>) -> PostOrderIter<BstNodeLink<K, V, M>> {
+        PostOrderIter::new(Some(node.clone()))
+    }
+
+    // Level-order (BFS) iterator over the subtree rooted at node
+    pub fn level_order(node: &BstNodeLink<K, V, M>) -> LevelOrderIter<BstNodeLink<K, V, M>> {
+        LevelOrderIter::new(Some(node.clone()))
+    }
+
+    // Check if a node is "nil" (no parent, no children, and no key)
+    #[allow(clippy::needless_return)]
+    fn is_nil(node: &Option<BstNodeLink<K, V, M>>) -> bool {
+        match node {
+            None => true,
+            Some(x) => {
+                if x.borrow().parent.is_none() || x.borrow().left.is_none() || x.borrow().right.is_none() {
+                    return true;
+                }
+                return false;
+            }
+        }
+    }
+
+    // Helper function to check if two nodes are equal by comparing their keys
+    #[allow(clippy::needless_return)]
+    fn is_node_match_option(node1: Option<BstNodeLink<K, V, M>>, node2: Option<BstNodeLink<K, V, M>>) -> bool {
+        if node1.is_none() && node2.is_none() {
+            return true;
+        }
+        if let Some(node1v) = node1 {
+            return node2.is_some_and(|x: BstNodeLink<K, V, M>| x.borrow().key == node1v.borrow().key);
+        }
+        return false;
+    }
+
+    // Check if two links point to the same node, keys alone are ambiguous with duplicates
+    fn is_node_match(anode: &BstNodeLink<K, V, M>, bnode: &BstNodeLink<K, V, M>) -> bool {
+        Rc::ptr_eq(anode, bnode)
+    }
+
+    /**
+     * Upgrade a weak reference to a strong reference
+     */
+    pub fn upgrade_weak_to_strong(node: Option<WeakBstNodeLink<K, V, M>>) -> Option<BstNodeLink<K, V, M>> {
+        match node {
+            None => None,
+            Some(x) => x.upgrade(),  
+        }
+    }
+
+    // Number of edges between node and the root, found by climbing the parent links
+    pub fn depth(node: &BstNodeLink<K, V, M>) -> usize {
+        let mut depth = 0;
+        let mut current = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
+        while let Some(parent) = current {
+            depth += 1;
+            current = BstNode::upgrade_weak_to_strong(parent.borrow().parent.clone());
+        }
+        depth
+    }
+}
+
+// Constructors for any node flavour, the metadata starts at its default value
+impl<K: Ord, V, M: Default> BstNode<K, V, M> {
+    // Create a new node holding a key and its associated value
+    fn new_entry(key: K, value: V) -> Self {
+        BstNode::new_entry_with_meta(key, value, M::default())
+    }
+
+    // Public function to create a new node link with a key and a value
+    pub fn new_bst_nodelink_entry(key: K, value: V) -> BstNodeLink<K, V, M> {
+        let current_node = BstNode::new_entry(key, value);
+        Rc::new(RefCell::new(current_node))
+    }
+
+    /**
+     * Insert a key with its value, keys are unique here:
+     * when the key is already present its value is replaced and the old value returned
+     */
+    pub fn insert_entry(root: &mut Option<BstNodeLink<K, V, M>>, key: K, value: V) -> Option<V> {
+        match BstNode::insert_unique(root, key, value) {
+            Ok(_) => None,
+            Err((node, value)) => node.borrow_mut().value.replace(value),
+        }
+    }
+
+    /**
+     * Attach a new leaf for key unless the key is already present
+     * Returns Ok(new leaf), or Err((existing node, value)) handing the value back untouched,
+     * balanced variants use the new leaf as the starting point of their fixup
+     */
+    pub fn insert_unique(
+        root: &mut Option<BstNodeLink<K, V, M>>,
+        key: K,
+        value: V,
+    ) -> InsertResult<K, V, M> {
+        match BstNode::find_slot(root, &key) {
+            Slot::Occupied(node) => Err((node, value)),
+            Slot::Vacant(parent, is_left) => Ok(BstNode::attach(root, parent, is_left, key, value)),
+        }
+    }
+
+    /**
+     * Hang a new leaf in a slot found by `find_slot` and return it, the sizes up to the
+     * root are refreshed. The slot must still be empty, i.e. no insert or delete in between
+     */
+    pub fn attach(
+        root: &mut Option<BstNodeLink<K, V, M>>,
+        parent: Option<BstNodeLink<K, V, M>>,
+        is_left: bool,
+        key: K,
+        value: V,
+    ) -> BstNodeLink<K, V, M> {
+        BstNode::attach_with_meta(root, parent, is_left, key, value, M::default())
+    }
+}
+
+impl<K: Ord + Clone, V: Clone, M: Clone> BstNode<K, V, M> {
+    /**
+     * Get a copy of the node link
+     */
+    pub fn get_bst_nodelink_copy(&self) -> BstNodeLink<K, V, M> {
+        Rc::new(RefCell::new(self.clone()))
+    }
+}
+
+/**
+ * Iterator returned by `BstNode::range`, it starts at the first node inside the range
+ * and follows `tree_successor` until a key passes the upper bound
+ */
+pub struct BstRange<K, V, M, R> {
+    next: Option<BstNodeLink<K, V, M>>,
+    range: R,
+}
+
+impl<K: Ord, V, M, R: RangeBounds<K>> Iterator for BstRange<K, V, M, R> {
+    type Item = BstNodeLink<K, V, M>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        let below_end = match (self.range.end_bound(), node.borrow().key.as_ref()) {
+            (_, None) => false,
+            (Bound::Included(end), Some(key)) => key <= end,
+            (Bound::Excluded(end), Some(key)) => key < end,
+            (Bound::Unbounded, Some(_)) => true,
+        };
+        if !below_end {
+            return None;
+        }
+        self.next = BstNode::tree_successor(&node);
+        Some(node)
+    }
+}
+
+/**
+ * Double-ended in-order iterator returned by `BstNode::in_order`
+ * The front follows `tree_successor` from the minimum, the back follows `tree_predecessor`
+ * from the maximum, and iteration ends once both ends meet on the same node
+ */
+pub struct BstInOrder<K, V, M> {
+    front: Option<BstNodeLink<K, V, M>>,
+    back: Option<BstNodeLink<K, V, M>>,
+}
+
+impl<K: Ord, V, M> Iterator for BstInOrder<K, V, M> {
+    type Item = BstNodeLink<K, V, M>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.front.take()?;
+        if self.back.as_ref().is_some_and(|back| Rc::ptr_eq(back, &node)) {
+            self.back = None;
+        } else {
+            self.front = BstNode::tree_successor(&node);
+        }
+        Some(node)
+    }
+}
+
+impl<K: Ord, V, M> DoubleEndedIterator for BstInOrder<K, V, M> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.back.take()?;
+        if self.front.as_ref().is_some_and(|front| Rc::ptr_eq(front, &node)) {
+            self.front = None;
+        } else {
+            self.back = BstNode::tree_predecessor(&node);
+        }
+        Some(node)
+    }
+}
+
+/**
+ * Owning handle for a BST, it keeps the root link private so only the real root
+ * is ever passed down to `BstNode::insert`, `BstNode::delete` and `BstNode::transplant`,
+ * and it tracks the number of keys so `len` is O(1)
+ * Its `DuplicatePolicy` decides what inserting a present key does, `len`, `count`,
+ * `remove`, the iterators, select and rank all count every copy of a key
+ */
+#[derive(Debug)]
+pub struct Bst<K = i32> {
+    root: Option<BstNodeLink<K>>,
+    len: usize,
+    policy: DuplicatePolicy,
+}
+
+impl<K: Ord + Clone> Bst<K> {
+    // Empty tree that allows duplicate nodes, like BstNode::insert
+    pub fn new() -> Self {
+        Bst::with_policy(DuplicatePolicy::Allow)
+    }
+
+    pub fn with_policy(policy: DuplicatePolicy) -> Self {
+        Bst { root: None, len: 0, policy }
+    }
+
+    // Handle for a tree built elsewhere, the length is read from the root size
+    fn from_root(root: Option<BstNodeLink<K>>, policy: DuplicatePolicy) -> Self {
+        let len = BstNode::subtree_size(&root);
+        Bst { root, len, policy }
+    }
+
+    /**
+     * Balanced tree from sorted keys, see BstNode::from_sorted for the input policy
+     * Strict and Dedup both leave unique keys, so the handle gets DuplicatePolicy::Reject
+     * and later inserts keep the tree a set
+     */
+    pub fn from_sorted<I: IntoIterator<Item = K>>(keys: I, policy: SortedInputPolicy) -> Result<Self, BuildError> {
+        Ok(Bst::from_root(BstNode::from_sorted(keys, policy)?, DuplicatePolicy::Reject))
+    }
+
+    pub fn policy(&self) -> DuplicatePolicy {
+        self.policy
+    }
+
+    // Root of the tree, e.g. for generate_dotfile_bst
+    pub fn root(&self) -> Option<&BstNodeLink<K>> {
+        self.root.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Insert a key, a key that is already present is handled by the policy
+     * Returns true if the tree now holds one more key, i.e. false for Reject and Replace
+     * when the key was there already
+     */
+    pub fn insert(&mut self, key: K) -> bool {
+        if self.policy != DuplicatePolicy::Allow {
+            if let Some(existing) = self.root.as_ref().and_then(|root| BstNode::tree_search(root, &key)) {
+                match self.policy {
+                    DuplicatePolicy::Reject => return false,
+                    DuplicatePolicy::Replace => {
+                        existing.borrow_mut().key = Some(key);
+                        return false;
+                    }
+                    // Count, Allow never searches first
+                    _ => {
+                        existing.borrow_mut().count += 1;
+                        BstNode::update_size_upward(Some(existing));
+                        self.len += 1;
+                        return true;
+                    }
+                }
+            }
+        }
+        BstNode::insert(&mut self.root, key);
+        self.len += 1;
+        true
+    }
+
+    /**
+     * Remove one copy of key, return false if the key is not in the tree
+     * A counted key only loses one from its count, its node goes when the count reaches 0
+     */
+    pub fn remove(&mut self, key: &K) -> bool {
+        match self.root.as_ref().and_then(|root| BstNode::tree_search(root, key)) {
+            Some(node) => {
+                if node.borrow().count > 1 {
+                    node.borrow_mut().count -= 1;
+                    BstNode::update_size_upward(Some(node));
+                } else {
+                    BstNode::delete(&mut self.root, &node);
+                }
+                self.len -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Remove every copy of key, return how many were removed
+    pub fn remove_all(&mut self, key: &K) -> usize {
+        self.range_delete((Bound::Included(key.clone()), Bound::Included(key.clone())))
+    }
+
+    // Number of copies of key in the tree, duplicate nodes and counts alike
+    pub fn count(&self, key: &K) -> usize {
+        self.range_count((Bound::Included(key.clone()), Bound::Included(key.clone())))
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.root.as_ref().and_then(|root| BstNode::tree_search(root, key)).is_some()
+    }
+
+    // Smallest key in the tree
+    pub fn min(&self) -> Option<K> {
+        let root = self.root.as_ref()?;
+        let min_node = BstNode::minimum(root);
+        let key = min_node.borrow().key.clone();
+        key
+    }
+
+    // Largest key in the tree
+    pub fn max(&self) -> Option<K> {
+        let root = self.root.as_ref()?;
+        let max_node = BstNode::maximum(root);
+        let key = max_node.borrow().key.clone();
+        key
+    }
+
+    // All keys in order, a counted key repeats count times, iterate with `.rev()` for descending order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = K> {
+        let nodes = self.root.as_ref().map(BstNode::in_order);
+        nodes.into_iter().flatten().flat_map(Bst::copies)
+    }
+
+    // Keys inside range, in order, a counted key repeats count times
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> impl Iterator<Item = K> {
+        let nodes = self.root.as_ref().map(|root| BstNode::range(root, range));
+        nodes.into_iter().flatten().flat_map(Bst::copies)
+    }
+
+    // The key of node repeated by its count
+    fn copies(node: BstNodeLink<K>) -> std::iter::RepeatN<K> {
+        let node_borrow = node.borrow();
+        std::iter::repeat_n(node_borrow.key.clone().unwrap(), node_borrow.count)
+    }
+
+    pub fn range_count<R: RangeBounds<K>>(&self, range: R) -> usize {
+        match self.root.as_ref() {
+            Some(root) => BstNode::range_count(root, range),
+            None => 0,
+        }
+    }
+
+    // Remove every key inside range, return how many keys were removed
+    pub fn range_delete<R: RangeBounds<K>>(&mut self, range: R) -> usize {
+        let removed = BstNode::range_delete(&mut self.root, range);
+        self.len -= removed;
+        removed
+    }
+
+    // k-th smallest key, counting from 0
+    pub fn select(&self, k: usize) -> Option<K> {
+        let node = BstNode::select(self.root.as_ref()?, k)?;
+        let key = node.borrow().key.clone();
+        key
+    }
+
+    // Number of keys strictly smaller than key
+    pub fn rank(&self, key: &K) -> usize {
+        match self.root.as_ref() {
+            Some(root) => BstNode::rank(root, key),
+            None => 0,
+        }
+    }
+
+    // Split into the keys smaller than key and the rest, see BstNode::split
+    pub fn split(self, key: &K) -> (Bst<K>, Bst<K>) {
+        let (smaller, rest) = BstNode::split(self.root, key);
+        let smaller_len = BstNode::subtree_size(&smaller);
+        let rest_len = self.len - smaller_len;
+        (Bst::from_root(smaller, self.policy), Bst { root: rest, len: rest_len, policy: self.policy })
+    }
+
+    /**
+     * Join two trees with the same duplicate policy whose key ranges do not overlap,
+     * panics otherwise, see BstNode::join
+     */
+    pub fn join(left: Bst<K>, right: Bst<K>) -> Bst<K> {
+        Bst::try_join(left, right).unwrap_or_else(|_| {
+            panic!("join needs the same duplicate policy on both trees and every key of the left tree to be at most every key of the right tree")
+        })
+    }
+
+    // Like join, but differing policies or overlapping key ranges hand both trees back instead of panicking
+    pub fn try_join(left: Bst<K>, right: Bst<K>) -> Result<Bst<K>, (Bst<K>, Bst<K>)> {
+        if left.policy != right.policy {
+            return Err((left, right));
+        }
+        let len = left.len + right.len;
+        let policy = left.policy;
+        match BstNode::try_join(left.root, right.root) {
+            Ok(root) => Ok(Bst { root, len, policy }),
+            Err((left_root, right_root)) => Err((
+                Bst { root: left_root, len: left.len, policy },
+                Bst { root: right_root, len: right.len, policy: right.policy },
+            )),
+        }
+    }
+
+    // Keys in self or other, as a new balanced tree
+    pub fn union(&self, other: &Bst<K>) -> Bst<K> {
+        Bst::from_root(BstNode::union(&self.root, &other.root), self.policy)
+    }
+
+    // Keys in both self and other, as a new balanced tree
+    pub fn intersection(&self, other: &Bst<K>) -> Bst<K> {
+        Bst::from_root(BstNode::intersection(&self.root, &other.root), self.policy)
+    }
+
+    // Keys in self but not in other, as a new balanced tree
+    pub fn difference(&self, other: &Bst<K>) -> Bst<K> {
+        Bst::from_root(BstNode::difference(&self.root, &other.root), self.policy)
+    }
+
+    // Keys in exactly one of self and other, as a new balanced tree
+    pub fn symmetric_difference(&self, other: &Bst<K>) -> Bst<K> {
+        Bst::from_root(BstNode::symmetric_difference(&self.root, &other.root), self.policy)
+    }
+
+    pub fn is_subset(&self, other: &Bst<K>) -> bool {
+        BstNode::is_subset(&self.root, &other.root)
+    }
+
+    pub fn is_superset(&self, other: &Bst<K>) -> bool {
+        BstNode::is_superset(&self.root, &other.root)
+    }
+
+    pub fn is_disjoint(&self, other: &Bst<K>) -> bool {
+        BstNode::is_disjoint(&self.root, &other.root)
+    }
+
+    // Cursor on the smallest key, or on the ghost position of an empty tree
+    pub fn cursor_front(&self) -> Cursor<'_, K> {
+        Cursor::new(&self.root, self.root.as_ref().map(BstNode::minimum))
+    }
+
+    // Cursor on the largest key, or on the ghost position of an empty tree
+    pub fn cursor_back(&self) -> Cursor<'_, K> {
+        Cursor::new(&self.root, self.root.as_ref().map(BstNode::maximum))
+    }
+
+    // Cursor on a node holding key, or on the ghost position if there is none
+    pub fn cursor_at(&self, key: &K) -> Cursor<'_, K> {
+        Cursor::new(&self.root, self.root.as_ref().and_then(|root| BstNode::tree_search(root, key)))
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K> {
+        let current = self.root.as_ref().map(BstNode::minimum);
+        CursorMut::new(&mut self.root, &mut self.len, self.policy, current)
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K> {
+        let current = self.root.as_ref().map(BstNode::maximum);
+        CursorMut::new(&mut self.root, &mut self.len, self.policy, current)
+    }
+
+    pub fn cursor_at_mut(&mut self, key: &K) -> CursorMut<'_, K> {
+        let current = self.root.as_ref().and_then(|root| BstNode::tree_search(root, key));
+        CursorMut::new(&mut self.root, &mut self.len, self.policy, current)
+    }
+
+    // Rebalance in place with BstNode::rebalance, the nodes stay the same
+    pub fn rebalance(&mut self) {
+        BstNode::rebalance(&mut self.root);
+    }
+
+    // Drop every node and reset the length
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+}
+
+impl<K: Ord + Clone> Default for Bst<K> {
+    fn default() -> Self {
+        Bst::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::test_util::{assert_parent_links, assert_sizes};
+
+    fn build(keys: &[i32]) -> Option<BstNodeLink> {
+        let mut root = None;
+        for &key in keys {
+            BstNode::insert(&mut root, key);
+        }
+        root
+    }
+
+    // In-order keys collected by following tree_successor from the minimum
+    fn keys_by_successor(root: &Option<BstNodeLink>) -> Vec<i32> {
+        let mut keys = Vec::new();
+        let mut current = root.as_ref().map(BstNode::minimum);
+        while let Some(node) = current {
+            keys.push(node.borrow().key.unwrap());
+            current = BstNode::tree_successor(&node);
+        }
+        keys
+    }
+
+    #[test]
+    fn tree_search_returns_node_inside_tree() {
+        let root = build(&[15, 6, 18, 3, 7]);
+        let root_node = root.as_ref().unwrap();
+        let found = BstNode::tree_search(root_node, &6).unwrap();
+        assert!(Rc::ptr_eq(&found, root_node.borrow().left.as_ref().unwrap()));
+        assert!(BstNode::tree_search(root_node, &15).is_some_and(|n| Rc::ptr_eq(&n, root_node)));
+        assert!(BstNode::tree_search(root_node, &8).is_none());
+    }
+
+    #[test]
+    fn minimum_and_maximum_return_nodes_inside_tree() {
+        let root = build(&[15, 6, 18, 3, 7, 20]);
+        let root_node = root.as_ref().unwrap();
+        let min_node = BstNode::minimum(root_node);
+        let max_node = BstNode::maximum(root_node);
+        assert_eq!(min_node.borrow().key, Some(3));
+        assert_eq!(max_node.borrow().key, Some(20));
+        assert!(Rc::ptr_eq(&BstNode::get_root(&min_node), root_node));
+        assert!(Rc::ptr_eq(&BstNode::get_root(&max_node), root_node));
+    }
+
+    #[test]
+    fn search_then_successor() {
+        let root = build(&[15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9]);
+        let root_node = root.as_ref().unwrap();
+        let successor_of = |key: i32| {
+            let node = BstNode::tree_search(root_node, &key).unwrap();
+            BstNode::tree_successor(&node).map(|n| n.borrow().key.unwrap())
+        };
+        assert_eq!(successor_of(2), Some(3));
+        assert_eq!(successor_of(13), Some(15));
+        assert_eq!(successor_of(15), Some(17));
+        assert_eq!(successor_of(9), Some(13));
+        assert_eq!(successor_of(20), None);
+    }
+
+    #[test]
+    fn search_then_delete_each_shape() {
+        let keys = [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9];
+        // leaf, one child, two children with direct right child, two children deeper, root
+        for key in [2, 7, 18, 6, 15] {
+            let mut root = build(&keys);
+            let node = BstNode::tree_search(root.as_ref().unwrap(), &key).unwrap();
+            BstNode::delete(&mut root, &node);
+
+            let mut expected: Vec<i32> = keys.iter().copied().filter(|&k| k != key).collect();
+            expected.sort();
+            assert_eq!(keys_by_successor(&root), expected, "deleting {}", key);
+            assert!(BstNode::tree_search(root.as_ref().unwrap(), &key).is_none());
+            assert!(root.as_ref().unwrap().borrow().parent.is_none());
+        }
+    }
+
+    #[test]
+    fn search_then_predecessor() {
+        let root = build(&[15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9]);
+        let root_node = root.as_ref().unwrap();
+        let predecessor_of = |key: i32| {
+            let node = BstNode::tree_search(root_node, &key).unwrap();
+            BstNode::tree_predecessor(&node).map(|n| n.borrow().key.unwrap())
+        };
+        assert_eq!(predecessor_of(3), Some(2));
+        assert_eq!(predecessor_of(15), Some(13));
+        assert_eq!(predecessor_of(17), Some(15));
+        assert_eq!(predecessor_of(9), Some(7));
+        assert_eq!(predecessor_of(2), None);
+    }
+
+    #[test]
+    fn predecessor_delete_keeps_order_and_uses_left_subtree() {
+        let keys = [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9];
+        for key in [15, 6, 3, 18] {
+            let mut root = build(&keys);
+            let node = BstNode::tree_search(root.as_ref().unwrap(), &key).unwrap();
+            BstNode::delete_with(&mut root, &node, DeleteStrategy::Predecessor);
+
+            let mut expected: Vec<i32> = keys.iter().copied().filter(|&k| k != key).collect();
+            expected.sort();
+            assert_eq!(keys_by_successor(&root), expected, "deleting {}", key);
+        }
+        // the predecessor takes the place of the deleted root
+        let mut root = build(&keys);
+        let node = BstNode::tree_search(root.as_ref().unwrap(), &15).unwrap();
+        BstNode::delete_with(&mut root, &node, DeleteStrategy::Predecessor);
+        assert_eq!(root.as_ref().unwrap().borrow().key, Some(13));
+    }
+
+    #[test]
+    fn floor_ceiling_and_strict_bounds() {
+        let root = build(&[15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9]);
+        let root_node = root.as_ref().unwrap();
+        let key_of = |node: Option<BstNodeLink>| node.map(|n| n.borrow().key.unwrap());
+
+        assert_eq!(key_of(BstNode::floor(root_node, &14)), Some(13));
+        assert_eq!(key_of(BstNode::floor(root_node, &13)), Some(13));
+        assert_eq!(key_of(BstNode::floor(root_node, &1)), None);
+        assert_eq!(key_of(BstNode::ceiling(root_node, &14)), Some(15));
+        assert_eq!(key_of(BstNode::ceiling(root_node, &13)), Some(13));
+        assert_eq!(key_of(BstNode::ceiling(root_node, &21)), None);
+
+        assert_eq!(key_of(BstNode::lower_bound(root_node, &13)), Some(9));
+        assert_eq!(key_of(BstNode::lower_bound(root_node, &2)), None);
+        assert_eq!(key_of(BstNode::upper_bound(root_node, &13)), Some(15));
+        assert_eq!(key_of(BstNode::upper_bound(root_node, &20)), None);
+    }
+
+    #[test]
+    fn ceiling_feeds_tree_successor() {
+        let root = build(&[15, 6, 18, 3, 7, 17, 20]);
+        let start = BstNode::ceiling(root.as_ref().unwrap(), &8).unwrap();
+        let next = BstNode::tree_successor(&start).unwrap();
+        assert_eq!(start.borrow().key, Some(15));
+        assert_eq!(next.borrow().key, Some(17));
+    }
+
+    fn keys_in<R: RangeBounds<i32>>(nodes: BstRange<i32, (), (), R>) -> Vec<i32> {
+        nodes.map(|n| n.borrow().key.unwrap()).collect()
+    }
+
+    #[test]
+    fn range_follows_bounds() {
+        let root = build(&[15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9]);
+        let root_node = root.as_ref().unwrap();
+
+        assert_eq!(keys_in(BstNode::range(root_node, 4..15)), vec![4, 6, 7, 9, 13]);
+        assert_eq!(keys_in(BstNode::range(root_node, 5..=17)), vec![6, 7, 9, 13, 15, 17]);
+        assert_eq!(keys_in(BstNode::range(root_node, ..4)), vec![2, 3]);
+        assert_eq!(keys_in(BstNode::range(root_node, 18..)), vec![18, 20]);
+        assert_eq!(keys_in(BstNode::range(root_node, (Bound::Excluded(7), Bound::Excluded(13)))), vec![9]);
+        assert!(keys_in(BstNode::range(root_node, 10..13)).is_empty());
+        assert_eq!(BstNode::range_count(root_node, ..), 11);
+    }
+
+    #[test]
+    fn range_delete_removes_only_the_range() {
+        let mut root = build(&[15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9]);
+        assert_eq!(BstNode::range_delete(&mut root, 6..=17), 6);
+        assert_eq!(keys_by_successor(&root), vec![2, 3, 4, 18, 20]);
+
+        let mut tree: Bst = Bst::new();
+        for key in [5, 1, 9, 3, 7] {
+            tree.insert(key);
+        }
+        assert_eq!(tree.range_delete(..=5), 3);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.range(..).collect::<Vec<_>>(), vec![7, 9]);
+    }
+
+    #[test]
+    fn in_order_is_double_ended() {
+        let root = build(&[15, 6, 18, 3, 7, 17, 20]);
+        let root_node = root.as_ref().unwrap();
+        let key_of = |node: BstNodeLink| node.borrow().key.unwrap();
+
+        let forward: Vec<i32> = BstNode::in_order(root_node).map(key_of).collect();
+        let backward: Vec<i32> = BstNode::in_order(root_node).rev().map(key_of).collect();
+        assert_eq!(forward, vec![3, 6, 7, 15, 17, 18, 20]);
+        assert_eq!(backward, vec![20, 18, 17, 15, 7, 6, 3]);
+
+        // both ends meet in the middle without yielding a node twice
+        let mut iter = BstNode::in_order(root_node);
+        let mut mixed = Vec::new();
+        while let Some(front) = iter.next() {
+            mixed.push(key_of(front));
+            if let Some(back) = iter.next_back() {
+                mixed.push(key_of(back));
+            }
+        }
+        assert_eq!(mixed, vec![3, 20, 6, 18, 7, 17, 15]);
+    }
+
+    #[test]
+    fn bst_handle_tracks_root_and_len() {
+        let mut tree: Bst = Bst::new();
+        assert!(tree.is_empty() && tree.root().is_none());
+        assert_eq!((tree.min(), tree.max()), (None, None));
+        for key in [42, 17, 68, 9, 33] {
+            tree.insert(key);
+        }
+        assert_eq!(tree.len(), 5);
+        assert_eq!((tree.min(), tree.max()), (Some(9), Some(68)));
+        assert!(tree.contains(&33) && !tree.contains(&34));
+
+        // removing the root moves the root link to the successor
+        assert!(tree.remove(&42));
+        assert!(!tree.remove(&42));
+        assert_eq!(tree.root().unwrap().borrow().key, Some(68));
+        assert!(tree.root().unwrap().borrow().parent.is_none());
+        assert_eq!(tree.len(), 4);
+        assert!(!tree.remove(&100));
+        assert_eq!(tree.len(), 4);
+
+        // the free functions still work on the handle's root
+        let node = BstNode::tree_search(tree.root().unwrap(), &17).unwrap();
+        assert_eq!(BstNode::tree_successor(&node).unwrap().borrow().key, Some(33));
+
+        tree.clear();
+        assert!(tree.is_empty() && tree.root().is_none());
+        assert!(!tree.contains(&17));
+    }
+
+    #[test]
+    fn bst_handle_iter() {
+        let mut tree: Bst = Bst::new();
+        assert_eq!(tree.iter().next(), None);
+        for key in [5, 1, 9, 3, 7] {
+            tree.insert(key);
+        }
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![1, 3, 5, 7, 9]);
+        assert_eq!(tree.iter().rev().take(2).collect::<Vec<_>>(), vec![9, 7]);
+    }
+
+    #[test]
+    fn rotations_keep_order_and_links() {
+        let mut root = build(&[15, 6, 18, 3, 7, 17, 20]);
+        let node = BstNode::tree_search(root.as_ref().unwrap(), &6).unwrap();
+
+        // inner rotation: 7 replaces 6 under 15, 6 picks up 7's empty left subtree
+        let pivot = BstNode::rotate_left(&mut root, &node).unwrap();
+        assert_eq!(pivot.borrow().key, Some(7));
+        assert_eq!(root.as_ref().unwrap().borrow().left.as_ref().unwrap().borrow().key, Some(7));
+        assert_parent_links(root.as_ref().unwrap());
+        assert_eq!(keys_by_successor(&root), vec![3, 6, 7, 15, 17, 18, 20]);
+
+        // rotating back restores the original shape
+        BstNode::rotate_right(&mut root, &pivot).unwrap();
+        assert!(root.as_ref().unwrap().borrow().left.as_ref().is_some_and(|n| Rc::ptr_eq(n, &node)));
+        assert_parent_links(root.as_ref().unwrap());
+
+        // rotating the root updates the root link
+        let old_root = root.clone().unwrap();
+        let new_root = BstNode::rotate_right(&mut root, &old_root).unwrap();
+        assert!(Rc::ptr_eq(root.as_ref().unwrap(), &new_root));
+        assert_eq!(new_root.borrow().key, Some(6));
+        assert_parent_links(&new_root);
+        assert_eq!(keys_by_successor(&root), vec![3, 6, 7, 15, 17, 18, 20]);
+
+        let leaf = BstNode::tree_search(&new_root, &3).unwrap();
+        assert!(BstNode::rotate_left(&mut root, &leaf).is_none());
+        assert!(BstNode::rotate_right(&mut root, &leaf).is_none());
+    }
+
+    #[test]
+    fn sizes_follow_insert_delete_and_rotations() {
+        let keys = [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9];
+        let mut root = build(&keys);
+        assert_sizes(&root);
+        for key in [6, 15, 2] {
+            let node = BstNode::tree_search(root.as_ref().unwrap(), &key).unwrap();
+            BstNode::delete(&mut root, &node);
+            assert_sizes(&root);
+        }
+        let node = BstNode::tree_search(root.as_ref().unwrap(), &13).unwrap();
+        BstNode::delete_with(&mut root, &node, DeleteStrategy::Predecessor);
+        assert_sizes(&root);
+
+        let pivot = root.clone().unwrap();
+        BstNode::rotate_left(&mut root, &pivot);
+        assert_sizes(&root);
+        BstNode::rotate_right(&mut root, &pivot);
+        assert_sizes(&root);
+
+        let mut map_root: Option<BstNodeLink> = None;
+        for key in keys {
+            BstNode::insert_unique(&mut map_root, key, ()).unwrap();
+        }
+        assert_sizes(&map_root);
+        assert_eq!(map_root.unwrap().borrow().size, keys.len());
+    }
+
+    #[test]
+    fn manual_tree_sizes_after_recompute() {
+        let rootlink: BstNodeLink = BstNode::new_bst_nodelink(15);
+        rootlink.borrow_mut().add_left_child(&rootlink, 6);
+        rootlink.borrow_mut().add_right_child(&rootlink, 18);
+        if let Some(left_tree) = &rootlink.borrow().left {
+            left_tree.borrow_mut().add_left_child(left_tree, 3);
+            left_tree.borrow_mut().add_right_child(left_tree, 7);
+        }
+        BstNode::recompute_sizes(&rootlink);
+        assert_sizes(&Some(rootlink.clone()));
+        assert_eq!(BstNode::select(&rootlink, 2).unwrap().borrow().key, Some(7));
+    }
+
+    #[test]
+    fn select_and_rank() {
+        let keys = [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9];
+        let root = build(&keys);
+        let root_node = root.as_ref().unwrap();
+        let mut sorted = keys.to_vec();
+        sorted.sort();
+        for (k, key) in sorted.iter().enumerate() {
+            assert_eq!(BstNode::select(root_node, k).unwrap().borrow().key, Some(*key));
+            assert_eq!(BstNode::rank(root_node, key), k);
+        }
+        assert!(BstNode::select(root_node, keys.len()).is_none());
+        assert_eq!(BstNode::rank(root_node, &1), 0);
+        assert_eq!(BstNode::rank(root_node, &16), 8);
+        assert_eq!(BstNode::rank(root_node, &100), keys.len());
+
+        let mut tree: Bst = Bst::new();
+        for key in [5, 1, 9, 3, 7] {
+            tree.insert(key);
+        }
+        tree.remove(&5);
+        assert_eq!(tree.select(2), Some(7));
+        assert_eq!(tree.rank(&9), 3);
+        assert_eq!(tree.select(4), None);
+    }
+
+    #[test]
+    fn delete_everything_through_search() {
+        let keys = [50, 30, 70, 20, 40, 60, 80, 35, 45, 65];
+        let mut root = build(&keys);
+        for key in keys {
+            let node = BstNode::tree_search(root.as_ref().unwrap(), &key).unwrap();
+            BstNode::delete(&mut root, &node);
+        }
+        assert!(root.is_none());
+    }
+
+    #[test]
+    fn from_sorted_builds_balanced_tree() {
+        let root = BstNode::from_sorted(1..=15, SortedInputPolicy::Strict).unwrap().unwrap();
+        assert_eq!(root.borrow().key, Some(8));
+        assert_parent_links(&root);
+        assert_sizes(&Some(root.clone()));
+        assert_eq!(BstNode::in_order(&root).map(|n| n.borrow().key.unwrap()).collect::<Vec<_>>(), (1..=15).collect::<Vec<_>>());
+        // a full tree of 15 nodes has every leaf at depth 3
+        for leaf in BstNode::pre_order(&root).filter(|n| n.borrow().left.is_none() && n.borrow().right.is_none()) {
+            assert_eq!(BstNode::depth(&leaf), 3);
+        }
+
+        let mut root = Some(root);
+        BstNode::insert(&mut root, 16);
+        assert_eq!(keys_by_successor(&root).last(), Some(&16));
+        assert!(BstNode::<i32>::from_sorted(Vec::new(), SortedInputPolicy::Strict).unwrap().is_none());
+    }
+
+    #[test]
+    fn from_sorted_policies() {
+        assert_eq!(BstNode::from_sorted([1, 2, 2, 3], SortedInputPolicy::Strict).unwrap_err(), BuildError::Duplicate { index: 2 });
+        assert_eq!(BstNode::from_sorted([1, 3, 2], SortedInputPolicy::Dedup).unwrap_err(), BuildError::Unsorted { index: 2 });
+        let tree = Bst::from_sorted([1, 1, 2, 3, 3, 3, 4], SortedInputPolicy::Dedup).unwrap();
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn from_sorted_keeps_a_set() {
+        for policy in [SortedInputPolicy::Strict, SortedInputPolicy::Dedup] {
+            let mut tree = Bst::from_sorted([1, 2, 3], policy).unwrap();
+            assert_eq!(tree.policy(), DuplicatePolicy::Reject);
+            assert!(!tree.insert(2));
+            assert!(tree.insert(4));
+            assert_eq!(tree.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn build_error_works_with_question_mark() {
+        fn build_boxed(keys: &[i32]) -> Result<usize, Box<dyn std::error::Error>> {
+            let tree = Bst::from_sorted(keys.iter().copied(), SortedInputPolicy::Strict)?;
+            Ok(tree.len())
+        }
+        assert_eq!(build_boxed(&[1, 2, 3]).unwrap(), 3);
+        let error = build_boxed(&[1, 3, 2]).unwrap_err();
+        assert_eq!(error.to_string(), "key at index 2 is smaller than the one before it");
+    }
+
+    #[test]
+    fn from_sorted_million_keys() {
+        let tree = Bst::from_sorted(0..1_000_000, SortedInputPolicy::Strict).unwrap();
+        assert_eq!(tree.len(), 1_000_000);
+        assert_eq!(tree.select(765_432), Some(765_432));
+        assert_eq!(tree.min(), Some(0));
+        assert_eq!(tree.max(), Some(999_999));
+    }
+
+    #[test]
+    fn rebalance_degenerate_tree_in_place() {
+        let keys: Vec<i32> = (0..1000).collect();
+        let mut tree = Bst::new();
+        for &key in &keys {
+            tree.insert(key);
+        }
+        let nodes_before: Vec<BstNodeLink> = BstNode::in_order(tree.root().unwrap()).collect();
+        tree.rebalance();
+
+        let root = tree.root().unwrap().clone();
+        assert_parent_links(&root);
+        assert_sizes(&Some(root.clone()));
+        let nodes_after: Vec<BstNodeLink> = BstNode::in_order(&root).collect();
+        assert_eq!(nodes_after.len(), keys.len());
+        assert!(nodes_before.iter().zip(&nodes_after).all(|(before, after)| Rc::ptr_eq(before, after)));
+        // 1000 nodes fit in 10 levels
+        let height = BstNode::level_order(&root).map(|node| BstNode::depth(&node)).max().unwrap();
+        assert_eq!(height, 9);
+        assert_eq!(tree.iter().collect::<Vec<_>>(), keys);
+
+        let mut empty: Option<BstNodeLink> = None;
+        BstNode::rebalance(&mut empty);
+        assert!(empty.is_none());
+    }
+
+    #[test]
+    fn rebalance_counted_tree_by_node_count() {
+        // 100 nodes fit in 7 levels, the 28 extra copies of 50 must not change that
+        let mut tree = Bst::with_policy(DuplicatePolicy::Count);
+        for key in 0..100 {
+            tree.insert(key);
+        }
+        for _ in 0..28 {
+            tree.insert(50);
+        }
+        assert_eq!(tree.len(), 128);
+        tree.rebalance();
+
+        let root = tree.root().unwrap().clone();
+        assert_parent_links(&root);
+        assert_sizes(&Some(root.clone()));
+        assert_eq!(root.borrow().size, 128);
+        let height = BstNode::level_order(&root).map(|node| BstNode::depth(&node)).max().unwrap();
+        assert_eq!(height, 6);
+        assert_eq!(tree.count(&50), 29);
+        assert_eq!(tree.iter().count(), 128);
+    }
+
+    #[test]
+    fn split_and_join_reuse_nodes() {
+        let keys = [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9];
+        for pivot in [1, 2, 8, 13, 15, 16, 21] {
+            let root = build(&keys);
+            let nodes_before: Vec<BstNodeLink> = BstNode::in_order(root.as_ref().unwrap()).collect();
+            let (smaller, rest) = BstNode::split(root, &pivot);
+
+            let mut expected: Vec<i32> = keys.to_vec();
+            expected.sort();
+            let cut = expected.iter().filter(|&&k| k < pivot).count();
+            assert_eq!(keys_by_successor(&smaller), expected[..cut], "split at {}", pivot);
+            assert_eq!(keys_by_successor(&rest), expected[cut..], "split at {}", pivot);
+            for part in [&smaller, &rest].into_iter().flatten() {
+                assert_parent_links(part);
+            }
+            assert_sizes(&smaller);
+            assert_sizes(&rest);
+
+            let joined = BstNode::join(smaller, rest);
+            assert_eq!(keys_by_successor(&joined), expected);
+            assert_parent_links(joined.as_ref().unwrap());
+            assert_sizes(&joined);
+            let nodes_after: Vec<BstNodeLink> = BstNode::in_order(joined.as_ref().unwrap()).collect();
+            assert!(nodes_before.iter().zip(&nodes_after).all(|(before, after)| Rc::ptr_eq(before, after)));
+        }
+    }
+
+    #[test]
+    fn bst_handle_split_join() {
+        let mut tree = Bst::new();
+        for key in [50, 30, 70, 20, 40, 60, 80] {
+            tree.insert(key);
+        }
+        let (low, high) = tree.split(&55);
+        assert_eq!((low.len(), high.len()), (4, 3));
+        assert_eq!(high.iter().collect::<Vec<_>>(), vec![60, 70, 80]);
+        let tree = Bst::join(low, high);
+        assert_eq!(tree.len(), 7);
+        assert_eq!(tree.select(3), Some(50));
+    }
+
+    #[test]
+    #[should_panic(expected = "join needs every key of the left tree")]
+    fn join_rejects_overlapping_ranges() {
+        BstNode::join(build(&[5, 10]), build(&[7]));
+    }
+
+    #[test]
+    fn try_join_hands_overlapping_trees_back() {
+        let (left, right) = BstNode::try_join(build(&[5, 10]), build(&[7])).unwrap_err();
+        assert_eq!(keys_by_successor(&left), vec![5, 10]);
+        assert_eq!(keys_by_successor(&right), vec![7]);
+
+        let mut low = Bst::new();
+        let mut high = Bst::new();
+        for key in [3, 1, 8] {
+            low.insert(key);
+        }
+        high.insert(5);
+        let (low, high) = Bst::try_join(low, high).unwrap_err();
+        assert_eq!((low.len(), high.len()), (3, 1));
+        assert_eq!(low.iter().collect::<Vec<_>>(), vec![1, 3, 8]);
+        let joined = Bst::try_join(high, Bst::from_root(build(&[6, 9]), DuplicatePolicy::Allow)).unwrap();
+        assert_eq!(joined.iter().collect::<Vec<_>>(), vec![5, 6, 9]);
+        assert_eq!(joined.len(), 3);
+    }
+
+    #[test]
+    fn try_join_hands_back_trees_with_different_policies() {
+        let mut counted = Bst::with_policy(DuplicatePolicy::Count);
+        counted.insert(1);
+        counted.insert(1);
+        let set = Bst::from_sorted([5, 6], SortedInputPolicy::Strict).unwrap();
+        // the ranges fit, but the joined tree could not honour both policies
+        let (counted, set) = Bst::try_join(counted, set).unwrap_err();
+        assert_eq!((counted.policy(), counted.len()), (DuplicatePolicy::Count, 2));
+        assert_eq!((set.policy(), set.len()), (DuplicatePolicy::Reject, 2));
+        let joined = Bst::try_join(Bst::from_sorted([1, 2], SortedInputPolicy::Strict).unwrap(), set).unwrap();
+        assert_eq!(joined.policy(), DuplicatePolicy::Reject);
+        assert_eq!(joined.iter().collect::<Vec<_>>(), vec![1, 2, 5, 6]);
+    }
+
+    #[test]
+    fn set_operations_build_balanced_trees() {
+        // duplicates in the plain BST count once
+        let a = build(&[5, 1, 9, 3, 7, 3]);
+        let b = build(&[6, 3, 9, 12]);
+        let keys = |root: Option<BstNodeLink>| {
+            if let Some(exist) = &root {
+                assert_parent_links(exist);
+            }
+            assert_sizes(&root);
+            keys_by_successor(&root)
+        };
+        assert_eq!(keys(BstNode::union(&a, &b)), vec![1, 3, 5, 6, 7, 9, 12]);
+        assert_eq!(keys(BstNode::intersection(&a, &b)), vec![3, 9]);
+        assert_eq!(keys(BstNode::difference(&a, &b)), vec![1, 5, 7]);
+        assert_eq!(keys(BstNode::symmetric_difference(&a, &b)), vec![1, 5, 6, 7, 12]);
+        assert_eq!(keys(BstNode::intersection(&a, &None)), vec![]);
+
+        let union = BstNode::union(&a, &b).unwrap();
+        assert_eq!(union.borrow().key, Some(6));
+    }
+
+    #[test]
+    fn set_predicates() {
+        let small = Bst::from_sorted([2, 4], SortedInputPolicy::Strict).unwrap();
+        let large = Bst::from_sorted(1..=5, SortedInputPolicy::Strict).unwrap();
+        let other = Bst::from_sorted([6, 7], SortedInputPolicy::Strict).unwrap();
+        assert!(small.is_subset(&large) && !large.is_subset(&small));
+        assert!(large.is_superset(&small));
+        assert!(small.is_disjoint(&other) && !small.is_disjoint(&large));
+        assert!(Bst::new().is_subset(&small) && Bst::<i32>::new().is_disjoint(&Bst::new()));
+        assert_eq!(large.difference(&small).iter().collect::<Vec<_>>(), vec![1, 3, 5]);
+        assert_eq!(small.union(&other).len(), 4);
+    }
+
+    #[test]
+    fn duplicate_policies() {
+        let keys = [5, 3, 5, 8, 5, 3];
+        let filled = |policy: DuplicatePolicy| {
+            let mut tree = Bst::with_policy(policy);
+            let added = keys.iter().filter(|&&key| tree.insert(key)).count();
+            assert_eq!(added, tree.len());
+            tree
+        };
+
+        for policy in [DuplicatePolicy::Reject, DuplicatePolicy::Replace] {
+            let mut tree = filled(policy);
+            assert_eq!(tree.iter().collect::<Vec<_>>(), vec![3, 5, 8]);
+            assert_eq!(tree.count(&5), 1);
+            assert!(tree.remove(&5) && !tree.contains(&5));
+        }
+
+        for policy in [DuplicatePolicy::Count, DuplicatePolicy::Allow] {
+            let mut tree = filled(policy);
+            assert_eq!(tree.len(), 6);
+            assert_eq!(tree.iter().collect::<Vec<_>>(), vec![3, 3, 5, 5, 5, 8]);
+            assert_eq!(tree.iter().rev().take(2).collect::<Vec<_>>(), vec![8, 5]);
+            assert_eq!(tree.range(4..).collect::<Vec<_>>(), vec![5, 5, 5, 8]);
+            assert_eq!(tree.count(&5), 3);
+            assert_eq!((tree.select(2), tree.select(4), tree.select(5)), (Some(5), Some(5), Some(8)));
+            assert_eq!(tree.rank(&8), 5);
+            assert_sizes(&tree.root().cloned());
+
+            assert!(tree.remove(&5));
+            assert_eq!(tree.count(&5), 2);
+            assert_eq!(tree.remove_all(&5), 2);
+            assert_eq!(tree.remove_all(&5), 0);
+            assert_eq!(tree.iter().collect::<Vec<_>>(), vec![3, 3, 8]);
+            assert_eq!(tree.len(), 3);
+            assert_sizes(&tree.root().cloned());
+        }
+
+        // Count keeps a single node per key
+        let tree = filled(DuplicatePolicy::Count);
+        assert_eq!(BstNode::pre_order(tree.root().unwrap()).count(), 3);
+        assert_eq!(tree.root().unwrap().borrow().count, 3);
+    }
+
+    #[test]
+    fn million_node_degenerate_tree() {
+        // built by hand in O(n), BstNode::insert on sorted keys would be quadratic
+        const DEPTH: i32 = 1_000_000;
+        let root = BstNode::new_bst_nodelink(0);
+        let mut last = root.clone();
+        for key in 1..DEPTH {
+            last.borrow_mut().add_right_child(&last, key);
+            let next = last.borrow().right.clone().unwrap();
+            last = next;
+        }
+        BstNode::recompute_sizes(&root);
+        assert_eq!(root.borrow().size, DEPTH as usize);
+
+        let deepest = BstNode::tree_search(&root, &(DEPTH - 1)).unwrap();
+        assert!(Rc::ptr_eq(&deepest, &last));
+        assert!(Rc::ptr_eq(&BstNode::maximum(&root), &last));
+        assert!(Rc::ptr_eq(&BstNode::minimum(&root), &root));
+        assert!(Rc::ptr_eq(&BstNode::get_root(&last), &root));
+        assert_eq!(BstNode::select(&root, 765_432).unwrap().borrow().key, Some(765_432));
+
+        let mut tree = Some(root);
+        BstNode::insert(&mut tree, DEPTH);
+        assert_eq!(BstNode::maximum(tree.as_ref().unwrap()).borrow().key, Some(DEPTH));
+
+        // a live handle keeps the tail alive after the rest is dropped
+        drop(deepest);
+        drop(tree);
+        assert_eq!(last.borrow().key, Some(DEPTH - 1));
+        assert_eq!(BstNode::maximum(&last).borrow().key, Some(DEPTH));
+    }
+
+    #[test]
+    fn dotfile_of_deep_tree() {
+        let root = BstNode::new_bst_nodelink(0);
+        let mut last = root.clone();
+        for key in 1..100_000 {
+            last.borrow_mut().add_right_child(&last, key);
+            let next = last.borrow().right.clone().unwrap();
+            last = next;
         }
+        let dot_path = std::env::temp_dir().join("deep_bst_graph_test.dot");
+        crate::tool::generate_dotfile_bst(&root, dot_path.to_str().unwrap());
+        let dot = std::fs::read_to_string(&dot_path).unwrap();
+        assert_eq!(dot.lines().count(), 100_000 + 1);
+        assert!(dot.contains("\t99998--99999;\n"));
     }
 }
@@ -0,0 +1,387 @@
+use std::cell::{Ref, RefMut};
+use std::marker::PhantomData;
+
+use crate::structure::bst::{BstNode, BstNodeLink, Slot};
+
+// Map nodes carry their index in `BstMap::nodes` as metadata
+pub type MapNodeLink<K, V> = BstNodeLink<K, V, usize>;
+
+/**
+ * Ordered key/value map on top of `BstNode<K, V>`
+ * The nodes keep the same parent-linked layout as the integer BST, so insertion goes
+ * through `BstNode::find_slot`/`attach_with_meta` and removal through `BstNode::delete`.
+ * Every node is also kept in a flat list owned by the map: a node's `RefCell` is then
+ * reachable from `&self` without passing through another `RefCell`, which lets `get`
+ * hand out a `Ref` that lives as long as the borrow of the map
+ */
+#[derive(Debug)]
+pub struct BstMap<K, V> {
+    root: Option<MapNodeLink<K, V>>,
+    nodes: Vec<MapNodeLink<K, V>>,
+}
+
+impl<K: Ord, V> BstMap<K, V> {
+    pub fn new() -> Self {
+        BstMap { root: None, nodes: Vec::new() }
+    }
+
+    // Number of keys stored in the map
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /**
+     * Insert a key/value pair, if the key already exists its value is replaced
+     * and the previous value is returned
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match BstNode::find_slot(&self.root, &key) {
+            Slot::Occupied(node) => node.borrow_mut().value.replace(value),
+            Slot::Vacant(parent, is_left) => {
+                self.attach(parent, is_left, key, value);
+                None
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /**
+     * Value stored under key, borrowed from the node's `RefCell`
+     * The guard has to be dropped before the map is changed, like any `Ref`
+     */
+    pub fn get(&self, key: &K) -> Option<Ref<'_, V>> {
+        let index = self.find(key)?.borrow().meta;
+        Some(Ref::map(self.nodes[index].borrow(), |node| node.value.as_ref().unwrap()))
+    }
+
+    // Value stored under key, mutably borrowed for as long as the map is
+    pub fn get_mut(&mut self, key: &K) -> Option<RefMut<'_, V>> {
+        let index = self.find(key)?.borrow().meta;
+        Some(RefMut::map(self.nodes[index].borrow_mut(), |node| node.value.as_mut().unwrap()))
+    }
+
+    /**
+     * Remove a key from the map and return its value
+     * The node is unlinked with the regular `BstNode::delete`
+     */
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let node = self.find(key)?;
+        Some(self.unlink(&node))
+    }
+
+    /**
+     * Entry for key, found with a single descent, for in-place insert-or-update:
+     * `*map.entry(word).or_insert(0).get_mut() += 1`
+     */
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        match BstNode::find_slot(&self.root, &key) {
+            Slot::Occupied(node) => Entry::Occupied(OccupiedEntry { map: self, key, node }),
+            Slot::Vacant(parent, is_left) => Entry::Vacant(VacantEntry {
+                map: self,
+                key,
+                parent,
+                is_left,
+            }),
+        }
+    }
+
+    // Live node holding key, if any
+    fn find(&self, key: &K) -> Option<MapNodeLink<K, V>> {
+        BstNode::tree_search(self.root.as_ref()?, key)
+    }
+
+    // Hang a new node in an empty slot and add it to the node list
+    fn attach(&mut self, parent: Option<MapNodeLink<K, V>>, is_left: bool, key: K, value: V) -> MapNodeLink<K, V> {
+        let node = BstNode::attach_with_meta(&mut self.root, parent, is_left, key, value, self.nodes.len());
+        self.nodes.push(node.clone());
+        node
+    }
+
+    // Delete a live node from the tree and the node list, the last node takes over its index
+    fn unlink(&mut self, node: &MapNodeLink<K, V>) -> V {
+        BstNode::delete(&mut self.root, node);
+        let index = node.borrow().meta;
+        self.nodes.swap_remove(index);
+        if let Some(moved) = self.nodes.get(index) {
+            moved.borrow_mut().meta = index;
+        }
+        let value = node.borrow_mut().value.take().unwrap();
+        value
+    }
+}
+
+impl<K: Ord, V> Default for BstMap<K, V> {
+    fn default() -> Self {
+        BstMap::new()
+    }
+}
+
+// A view into one key of a `BstMap`, either holding a value or not yet
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+// Entry whose key is in the map, it holds the live node and the key passed to `entry`
+pub struct OccupiedEntry<'a, K, V> {
+    map: &'a mut BstMap<K, V>,
+    key: K,
+    node: MapNodeLink<K, V>,
+}
+
+// Entry whose key is missing, it remembers the empty slot found by the descent
+pub struct VacantEntry<'a, K, V> {
+    map: &'a mut BstMap<K, V>,
+    key: K,
+    parent: Option<MapNodeLink<K, V>>,
+    is_left: bool,
+}
+
+/**
+ * Value of an entry after `or_insert` and friends, it keeps the node link and the map
+ * borrowed for 'a. The value itself is reached through `RefCell` guards, so a guard
+ * has to be dropped before the map is used again: `*map.entry(word).or_insert(0).get_mut() += 1`
+ */
+pub struct ValueMut<'a, K, V> {
+    node: MapNodeLink<K, V>,
+    _map: PhantomData<&'a mut BstMap<K, V>>,
+}
+
+impl<'a, K, V> ValueMut<'a, K, V> {
+    fn new(node: MapNodeLink<K, V>) -> Self {
+        ValueMut { node, _map: PhantomData }
+    }
+
+    pub fn get(&self) -> Ref<'_, V> {
+        Ref::map(self.node.borrow(), |node| node.value.as_ref().unwrap())
+    }
+
+    pub fn get_mut(&mut self) -> RefMut<'_, V> {
+        RefMut::map(self.node.borrow_mut(), |node| node.value.as_mut().unwrap())
+    }
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    // Value of the entry, inserting default first if the key is missing
+    pub fn or_insert(self, default: V) -> ValueMut<'a, K, V> {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    // Like or_insert, the default is only computed when the key is missing
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> ValueMut<'a, K, V> {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> ValueMut<'a, K, V>
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    // Run f on the value if the key is present, a vacant entry passes through untouched
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(&mut entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    // The key passed to `BstMap::entry`, equal to the stored one
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn get(&self) -> Ref<'_, V> {
+        Ref::map(self.node.borrow(), |node| node.value.as_ref().unwrap())
+    }
+
+    pub fn get_mut(&mut self) -> RefMut<'_, V> {
+        RefMut::map(self.node.borrow_mut(), |node| node.value.as_mut().unwrap())
+    }
+
+    // Turn the entry into a handle on the value that keeps the map borrowed for 'a
+    pub fn into_mut(self) -> ValueMut<'a, K, V> {
+        ValueMut::new(self.node)
+    }
+
+    // Replace the value and return the old one
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(&mut self.get_mut(), value)
+    }
+
+    // Remove the key from the map and return its value
+    pub fn remove(self) -> V {
+        self.map.unlink(&self.node)
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    // Hang a new node in the slot found by `BstMap::entry`, no second descent
+    pub fn insert(self, value: V) -> ValueMut<'a, K, V> {
+        let node = self.map.attach(self.parent, self.is_left, self.key, value);
+        ValueMut::new(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_get_and_remove() {
+        let mut map = BstMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.insert(5, "five"), None);
+        assert_eq!(map.insert(3, "three"), None);
+        assert_eq!(map.insert(8, "eight"), None);
+        assert_eq!(map.insert(5, "FIVE"), Some("five"));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&5).as_deref(), Some(&"FIVE"));
+        assert!(map.get(&4).is_none());
+        assert!(map.contains_key(&3) && !map.contains_key(&4));
+
+        // the root has two children, so remove goes through the successor case
+        assert_eq!(map.remove(&5), Some("FIVE"));
+        assert_eq!(map.remove(&5), None);
+        assert_eq!(map.len(), 2);
+        assert!(!map.contains_key(&5));
+        assert_eq!(map.get(&8).as_deref(), Some(&"eight"));
+    }
+
+    #[test]
+    fn values_are_borrowed_through_guards() {
+        // values that are not Clone can still be read and changed in place
+        struct Log(Vec<i32>);
+        let mut map = BstMap::new();
+        map.insert(String::from("fox"), Log(vec![1, 2]));
+        map.insert(String::from("cat"), Log(vec![]));
+        assert_eq!(map.get(&String::from("fox")).unwrap().0.len(), 2);
+        map.get_mut(&String::from("fox")).unwrap().0.push(3);
+        assert!(map.get_mut(&String::from("dog")).is_none());
+        // two shared guards at once are fine
+        let (fox, cat) = (map.get(&String::from("fox")).unwrap(), map.get(&String::from("cat")).unwrap());
+        assert_eq!((fox.0.as_slice(), cat.0.len()), (&[1, 2, 3][..], 0));
+        drop((fox, cat));
+        // no borrow is left behind, the node can be borrowed again right away
+        let root = map.root.as_ref().unwrap();
+        assert_eq!(root.borrow().value.as_ref().map(|log| log.0.len()), Some(3));
+    }
+
+    #[test]
+    fn node_list_follows_removals() {
+        let mut map = BstMap::new();
+        for key in [50, 30, 70, 20, 40, 60, 80] {
+            map.insert(key, key + 1);
+        }
+        for key in [50, 20, 80] {
+            assert_eq!(map.remove(&key), Some(key + 1));
+        }
+        for (index, node) in map.nodes.iter().enumerate() {
+            assert_eq!(node.borrow().meta, index);
+        }
+        for key in [30, 40, 60, 70] {
+            assert_eq!(map.get(&key).as_deref(), Some(&(key + 1)));
+        }
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    fn entry_counts_words() {
+        let mut counts = BstMap::new();
+        for word in "the cat and the dog and the bird".split(' ') {
+            *counts.entry(word).or_insert(0).get_mut() += 1;
+        }
+        assert_eq!(counts.len(), 5);
+        assert_eq!(*counts.get(&"the").unwrap(), 3);
+        assert_eq!(*counts.get(&"and").unwrap(), 2);
+        assert_eq!(*counts.get(&"cat").unwrap(), 1);
+    }
+
+    #[test]
+    fn entry_and_modify_or_insert() {
+        let mut map = BstMap::new();
+        map.insert(2, String::from("two"));
+        map.entry(2).and_modify(|value| value.push('!')).or_insert_with(|| String::from("new"));
+        map.entry(1).and_modify(|value| value.push('!')).or_insert_with(|| String::from("one"));
+        assert_eq!(map.get(&2).unwrap().as_str(), "two!");
+        assert_eq!(map.get(&1).unwrap().as_str(), "one");
+        assert_eq!(map.entry(3).key(), &3);
+
+        match map.entry(2) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), "two!"),
+            Entry::Vacant(_) => panic!("2 should be occupied"),
+        }
+        assert_eq!(map.len(), 1);
+        assert!(!map.contains_key(&2));
+        assert_eq!(*map.entry(5).or_default().get(), "");
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn entry_on_existing_key() {
+        let mut map = BstMap::new();
+        for key in [4, 2, 6] {
+            map.insert(key, key * 10);
+        }
+        // or_insert_with keeps the stored value and never calls the default
+        let value = map.entry(2).or_insert_with(|| panic!("2 is already in the map"));
+        assert_eq!(*value.get(), 20);
+        *map.entry(6).and_modify(|value| *value += 1).or_insert(0).get_mut() += 100;
+        assert_eq!(*map.get(&6).unwrap(), 161);
+        assert_eq!(map.len(), 3);
+
+        match map.entry(4) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &4);
+                assert_eq!(entry.insert(41), 40);
+                *entry.get_mut() += 1;
+                assert_eq!(*entry.get(), 42);
+            }
+            Entry::Vacant(_) => panic!("4 should be occupied"),
+        }
+        assert_eq!(*map.get(&4).unwrap(), 42);
+
+        // a guard from one entry is gone before the tree is borrowed again
+        let mut value = map.entry(2).or_default();
+        *value.get_mut() = 7;
+        drop(value);
+        let root = map.root.as_ref().unwrap();
+        assert_eq!(root.borrow().value, Some(42));
+        assert_eq!(*map.get(&2).unwrap(), 7);
+    }
+}
+
@@ -0,0 +1,454 @@
+use std::cmp::Ordering;
+
+use crate::structure::bst::{BstNode, BstNodeLink, DuplicatePolicy};
+
+/**
+ * Read-only position in a BST, either on a node or on the "ghost" position (None)
+ * that sits after the largest key and before the smallest one, like the cursors of
+ * std's LinkedList. The cursor only keeps node links and borrows a `RefCell` for the
+ * length of one call, so it never causes a double-borrow panic, and it holds the root
+ * borrowed so the tree cannot change under it
+ */
+#[derive(Debug)]
+pub struct Cursor<'a, K, V = (), M = ()> {
+    root: &'a Option<BstNodeLink<K, V, M>>,
+    current: Option<BstNodeLink<K, V, M>>,
+}
+
+impl<'a, K: Ord, V, M> Cursor<'a, K, V, M> {
+    // Cursor on current, which must be a node of the tree under root (None for the ghost)
+    pub fn new(root: &'a Option<BstNodeLink<K, V, M>>, current: Option<BstNodeLink<K, V, M>>) -> Self {
+        Cursor { root, current }
+    }
+
+    // Node under the cursor, None on the ghost position
+    pub fn current(&self) -> Option<&BstNodeLink<K, V, M>> {
+        self.current.as_ref()
+    }
+
+    pub fn key(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        let node = self.current.as_ref()?;
+        let key = node.borrow().key.clone();
+        key
+    }
+
+    // Move to the in-order successor, from the largest key to the ghost and from the ghost to the smallest key
+    pub fn move_next(&mut self) {
+        self.current = match &self.current {
+            Some(node) => BstNode::tree_successor(node),
+            None => self.root.as_ref().map(BstNode::minimum),
+        };
+    }
+
+    // Move to the in-order predecessor, the mirror of move_next
+    pub fn move_prev(&mut self) {
+        self.current = match &self.current {
+            Some(node) => BstNode::tree_predecessor(node),
+            None => self.root.as_ref().map(BstNode::maximum),
+        };
+    }
+
+    // Move up to the parent, returns false and stays put at the root or on the ghost
+    pub fn move_parent(&mut self) -> bool {
+        let parent = self.current.as_ref().and_then(|node| BstNode::upgrade_weak_to_strong(node.borrow().parent.clone()));
+        move_to(&mut self.current, parent)
+    }
+
+    // Move down to the left child, returns false and stays put if there is none
+    pub fn move_left(&mut self) -> bool {
+        let left = self.current.as_ref().and_then(|node| node.borrow().left.clone());
+        move_to(&mut self.current, left)
+    }
+
+    // Move down to the right child, returns false and stays put if there is none
+    pub fn move_right(&mut self) -> bool {
+        let right = self.current.as_ref().and_then(|node| node.borrow().right.clone());
+        move_to(&mut self.current, right)
+    }
+}
+
+/**
+ * Position in a `Bst` that can also edit it: `remove_current` takes one copy of the key
+ * under the cursor, `remove_current_all` unlinks its node and `insert_before` /
+ * `insert_after` hang a new key next to it in key order,
+ * without searching from the root. Moves work like `Cursor`
+ */
+#[derive(Debug)]
+pub struct CursorMut<'a, K> {
+    root: &'a mut Option<BstNodeLink<K>>,
+    len: &'a mut usize,
+    policy: DuplicatePolicy,
+    current: Option<BstNodeLink<K>>,
+}
+
+impl<'a, K: Ord> CursorMut<'a, K> {
+    // Built by `Bst::cursor_front_mut` and friends, which hand over the tree's root and length
+    pub(crate) fn new(
+        root: &'a mut Option<BstNodeLink<K>>,
+        len: &'a mut usize,
+        policy: DuplicatePolicy,
+        current: Option<BstNodeLink<K>>,
+    ) -> Self {
+        CursorMut { root, len, policy, current }
+    }
+
+    pub fn current(&self) -> Option<&BstNodeLink<K>> {
+        self.current.as_ref()
+    }
+
+    pub fn key(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        let node = self.current.as_ref()?;
+        let key = node.borrow().key.clone();
+        key
+    }
+
+    // Read-only view of the same position
+    pub fn as_cursor(&self) -> Cursor<'_, K> {
+        Cursor::new(self.root, self.current.clone())
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = match &self.current {
+            Some(node) => BstNode::tree_successor(node),
+            None => self.root.as_ref().map(BstNode::minimum),
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = match &self.current {
+            Some(node) => BstNode::tree_predecessor(node),
+            None => self.root.as_ref().map(BstNode::maximum),
+        };
+    }
+
+    pub fn move_parent(&mut self) -> bool {
+        let parent = self.current.as_ref().and_then(|node| BstNode::upgrade_weak_to_strong(node.borrow().parent.clone()));
+        move_to(&mut self.current, parent)
+    }
+
+    pub fn move_left(&mut self) -> bool {
+        let left = self.current.as_ref().and_then(|node| node.borrow().left.clone());
+        move_to(&mut self.current, left)
+    }
+
+    pub fn move_right(&mut self) -> bool {
+        let right = self.current.as_ref().and_then(|node| node.borrow().right.clone());
+        move_to(&mut self.current, right)
+    }
+
+    /**
+     * Remove one copy of the key under the cursor and return it, like `Bst::remove`:
+     * a counted key only loses one from its count and the cursor stays on it, the last
+     * copy goes with its node and the cursor moves on to the successor. Nothing happens
+     * on the ghost
+     */
+    pub fn remove_current(&mut self) -> Option<K>
+    where
+        K: Clone,
+    {
+        let node = self.current.clone()?;
+        if node.borrow().count > 1 {
+            node.borrow_mut().count -= 1;
+            BstNode::update_size_upward(Some(node.clone()));
+            *self.len -= 1;
+            let key = node.borrow().key.clone();
+            return key;
+        }
+        self.remove_current_all()
+    }
+
+    /**
+     * Remove the node under the cursor with all copies of its key and return the key,
+     * the cursor moves on to the successor. Nothing happens on the ghost
+     */
+    pub fn remove_current_all(&mut self) -> Option<K> {
+        let node = self.current.take()?;
+        // delete moves nodes, not keys, so the successor link stays valid
+        self.current = BstNode::tree_successor(&node);
+        BstNode::delete(self.root, &node);
+        let mut node_borrow = node.borrow_mut();
+        *self.len -= node_borrow.count;
+        node_borrow.key.take()
+    }
+
+    /**
+     * Insert key right after the cursor in key order, the cursor stays where it is
+     * On the ghost the key becomes the new smallest key. The key is handed back when it
+     * does not fit between the current key and the next one. A key equal to one of
+     * them follows the tree's policy like `Bst::insert`: Allow hangs a new node, Count
+     * bumps the neighbour's count, Replace swaps the neighbour's key and Reject hands it back
+     */
+    pub fn insert_after(&mut self, key: K) -> Result<(), K> {
+        let next = match &self.current {
+            Some(node) => BstNode::tree_successor(node),
+            None => self.root.as_ref().map(BstNode::minimum),
+        };
+        if let Some(equal) = self.equal_neighbour(self.current.as_ref(), &key, next.as_ref()) {
+            return self.merge(equal, key);
+        }
+        if !self.fits(self.current.as_ref(), &key, next.as_ref()) {
+            return Err(key);
+        }
+        // the slot right after current is its empty right child, or the empty left child
+        // of the next node, which then sits in current's right subtree
+        let (parent, is_left) = match &self.current {
+            Some(node) if node.borrow().right.is_none() => (Some(node.clone()), false),
+            _ => (next, true),
+        };
+        BstNode::attach(self.root, parent, is_left, key, ());
+        *self.len += 1;
+        Ok(())
+    }
+
+    // Mirror of insert_after: on the ghost the key becomes the new largest key
+    pub fn insert_before(&mut self, key: K) -> Result<(), K> {
+        let prev = match &self.current {
+            Some(node) => BstNode::tree_predecessor(node),
+            None => self.root.as_ref().map(BstNode::maximum),
+        };
+        if let Some(equal) = self.equal_neighbour(prev.as_ref(), &key, self.current.as_ref()) {
+            return self.merge(equal, key);
+        }
+        if !self.fits(prev.as_ref(), &key, self.current.as_ref()) {
+            return Err(key);
+        }
+        let (parent, is_left) = match &self.current {
+            Some(node) if node.borrow().left.is_none() => (Some(node.clone()), true),
+            _ => (prev, false),
+        };
+        BstNode::attach(self.root, parent, is_left, key, ());
+        *self.len += 1;
+        Ok(())
+    }
+
+    // Neighbour holding a key equal to key, only looked for when the policy is not Allow
+    fn equal_neighbour(&self, low: Option<&BstNodeLink<K>>, key: &K, high: Option<&BstNodeLink<K>>) -> Option<BstNodeLink<K>> {
+        if self.policy == DuplicatePolicy::Allow {
+            return None;
+        }
+        let is_equal = |node: &&BstNodeLink<K>| node.borrow().key.as_ref().unwrap().cmp(key) == Ordering::Equal;
+        low.filter(is_equal).or(high.filter(is_equal)).cloned()
+    }
+
+    // Apply the policy to a key equal to the one in node, same outcomes as `Bst::insert`
+    fn merge(&mut self, node: BstNodeLink<K>, key: K) -> Result<(), K> {
+        match self.policy {
+            DuplicatePolicy::Reject | DuplicatePolicy::Allow => Err(key),
+            DuplicatePolicy::Replace => {
+                node.borrow_mut().key = Some(key);
+                Ok(())
+            }
+            DuplicatePolicy::Count => {
+                node.borrow_mut().count += 1;
+                BstNode::update_size_upward(Some(node));
+                *self.len += 1;
+                Ok(())
+            }
+        }
+    }
+
+    // Whether key may sit between the nodes low and high (either may be missing)
+    fn fits(&self, low: Option<&BstNodeLink<K>>, key: &K, high: Option<&BstNodeLink<K>>) -> bool {
+        let allow_equal = self.policy == DuplicatePolicy::Allow;
+        let in_order = |smaller: &K, larger: &K| match smaller.cmp(larger) {
+            Ordering::Less => true,
+            Ordering::Equal => allow_equal,
+            Ordering::Greater => false,
+        };
+        let low_ok = low.is_none_or(|node| in_order(node.borrow().key.as_ref().unwrap(), key));
+        let high_ok = high.is_none_or(|node| in_order(key, node.borrow().key.as_ref().unwrap()));
+        low_ok && high_ok
+    }
+}
+
+// Point current at target if there is one, report whether the cursor moved
+fn move_to<L>(current: &mut Option<L>, target: Option<L>) -> bool {
+    match target {
+        Some(node) => {
+            *current = Some(node);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structure::bst::{Bst, BstNode, DuplicatePolicy};
+    use crate::structure::test_util::assert_sizes;
+
+    fn tree(keys: &[i32]) -> Bst {
+        let mut tree = Bst::with_policy(DuplicatePolicy::Reject);
+        for &key in keys {
+            tree.insert(key);
+        }
+        tree
+    }
+
+    #[test]
+    fn cursor_walks_in_order_and_around_the_ghost() {
+        let tree = tree(&[15, 6, 18, 3, 7, 17, 20]);
+        let mut cursor = tree.cursor_front();
+        let mut keys = Vec::new();
+        while let Some(key) = cursor.key() {
+            keys.push(key);
+            cursor.move_next();
+        }
+        assert_eq!(keys, vec![3, 6, 7, 15, 17, 18, 20]);
+        cursor.move_prev();
+        assert_eq!(cursor.key(), Some(20));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.key(), Some(3));
+
+        let mut cursor = tree.cursor_at(&7);
+        assert!(cursor.move_parent());
+        assert_eq!(cursor.key(), Some(6));
+        assert!(cursor.move_parent() && !cursor.move_parent());
+        assert_eq!(cursor.key(), Some(15));
+        assert!(cursor.move_right() && cursor.move_left());
+        assert_eq!(cursor.key(), Some(17));
+        assert!(!cursor.move_left());
+        assert_eq!(tree.cursor_at(&8).key(), None);
+    }
+
+    #[test]
+    fn cursor_mut_removes_and_inserts() {
+        let mut tree = tree(&[15, 6, 18, 3, 7, 17, 20]);
+        let mut cursor = tree.cursor_at_mut(&6);
+        assert_eq!(cursor.remove_current(), Some(6));
+        assert_eq!(cursor.key(), Some(7));
+
+        assert_eq!(cursor.insert_after(10), Ok(()));
+        assert_eq!(cursor.insert_after(16), Err(16));
+        assert_eq!(cursor.insert_before(5), Ok(()));
+        assert_eq!(cursor.insert_before(7), Err(7));
+        assert_eq!(cursor.key(), Some(7));
+        cursor.move_next();
+        assert_eq!(cursor.key(), Some(10));
+        assert_eq!(cursor.insert_after(12), Ok(()));
+
+        // the ghost inserts at both ends
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.key(), None);
+        assert_eq!(cursor.insert_after(1), Ok(()));
+        assert_eq!(cursor.insert_before(25), Ok(()));
+        assert_eq!(cursor.as_cursor().key(), None);
+
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![1, 3, 5, 7, 10, 12, 15, 17, 18, 20, 25]);
+        assert_eq!(tree.len(), 11);
+        let root = tree.root().unwrap();
+        assert_eq!(root.borrow().size, 11);
+        for node in BstNode::pre_order(root) {
+            let size = BstNode::pre_order(&node).count();
+            assert_eq!(node.borrow().size, size);
+        }
+    }
+
+    #[test]
+    fn cursor_mut_follows_duplicate_policy() {
+        // Reject hands the key back
+        let mut tree = tree(&[3, 5, 7]);
+        let mut cursor = tree.cursor_at_mut(&5);
+        assert_eq!(cursor.insert_after(5), Err(5));
+        assert_eq!(cursor.insert_after(7), Err(7));
+        assert_eq!(tree.len(), 3);
+
+        // Allow hangs a second node
+        let mut tree = Bst::with_policy(DuplicatePolicy::Allow);
+        for key in [3, 5, 7] {
+            tree.insert(key);
+        }
+        let mut cursor = tree.cursor_at_mut(&5);
+        assert_eq!(cursor.insert_after(5), Ok(()));
+        assert_eq!(cursor.insert_before(5), Ok(()));
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![3, 5, 5, 5, 7]);
+        assert_eq!(BstNode::pre_order(tree.root().unwrap()).count(), 5);
+
+        // Count bumps the neighbour's count, on either side of the cursor
+        let mut tree = Bst::with_policy(DuplicatePolicy::Count);
+        for key in [3, 5, 7] {
+            tree.insert(key);
+        }
+        let mut cursor = tree.cursor_at_mut(&5);
+        assert_eq!(cursor.insert_after(5), Ok(()));
+        assert_eq!(cursor.insert_after(7), Ok(()));
+        assert_eq!(cursor.insert_before(3), Ok(()));
+        assert_eq!(tree.len(), 6);
+        assert_eq!((tree.count(&3), tree.count(&5), tree.count(&7)), (2, 2, 2));
+        assert_eq!(tree.root().unwrap().borrow().size, 6);
+        assert_eq!(BstNode::pre_order(tree.root().unwrap()).count(), 3);
+    }
+
+    #[test]
+    fn cursor_mut_removes_counted_copies_one_by_one() {
+        let mut tree = Bst::with_policy(DuplicatePolicy::Count);
+        for key in [5, 3, 7, 5, 5, 7] {
+            tree.insert(key);
+        }
+        let mut cursor = tree.cursor_at_mut(&5);
+        assert_eq!(cursor.remove_current(), Some(5));
+        assert_eq!(cursor.key(), Some(5));
+        assert_eq!(cursor.remove_current(), Some(5));
+        assert_eq!(cursor.remove_current(), Some(5));
+        // the last copy takes the node with it
+        assert_eq!(cursor.key(), Some(7));
+        assert_eq!(cursor.remove_current_all(), Some(7));
+        assert_eq!(cursor.key(), None);
+        assert_eq!(tree.len(), 1);
+        assert_eq!((tree.count(&5), tree.count(&7)), (0, 0));
+        assert_sizes(&tree.root().cloned());
+    }
+
+    #[test]
+    fn cursor_mut_replaces_under_replace_policy() {
+        use std::cmp::Ordering;
+
+        // ordered by id only, so replacing swaps in the new label
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        struct Tagged(i32, &'static str);
+        impl PartialOrd for Tagged {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Tagged {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let mut tree = Bst::with_policy(DuplicatePolicy::Replace);
+        for key in [Tagged(3, "old"), Tagged(5, "old"), Tagged(7, "old")] {
+            tree.insert(key);
+        }
+        let mut cursor = tree.cursor_at_mut(&Tagged(5, ""));
+        assert_eq!(cursor.insert_after(Tagged(5, "new")), Ok(()));
+        assert_eq!(cursor.insert_before(Tagged(3, "new")), Ok(()));
+        assert_eq!(cursor.key(), Some(Tagged(5, "new")));
+        assert_eq!(tree.len(), 3);
+        let labels: Vec<_> = tree.iter().map(|key| key.1).collect();
+        assert_eq!(labels, vec!["new", "new", "old"]);
+    }
+
+    #[test]
+    fn cursor_mut_drains_tree() {
+        let mut tree = tree(&[4, 2, 6, 1, 3, 5, 7]);
+        let mut cursor = tree.cursor_front_mut();
+        let mut removed = Vec::new();
+        while let Some(key) = cursor.remove_current() {
+            removed.push(key);
+        }
+        assert_eq!(removed, vec![1, 2, 3, 4, 5, 6, 7]);
+        assert!(tree.is_empty() && tree.root().is_none());
+    }
+}
@@ -0,0 +1,295 @@
+use std::fmt::{self, Display};
+
+use crate::structure::bst::{BstNode, BstNodeLink, Slot};
+use crate::tool::DotNodeStyle;
+
+/**
+ * Closed interval [start, end], ordered by start and then by end,
+ * so the tree keeps intervals sorted by their start point
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: Ord> Interval<T> {
+    // Panics when start > end
+    pub fn new(start: T, end: T) -> Self {
+        assert!(start <= end, "interval start must not be greater than its end");
+        Interval { start, end }
+    }
+
+    // Two closed intervals overlap when each one starts before the other one ends
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+impl<T: Display> Display for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.start, self.end)
+    }
+}
+
+// Largest end point found in the subtree of an interval tree node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MaxEnd<T>(pub T);
+
+// Interval nodes are drawn like plain BST nodes
+impl<T> DotNodeStyle for MaxEnd<T> {}
+
+// Interval tree nodes are `BstNode`s keyed by the interval, their metadata is the subtree's max end
+pub type IntervalNodeLink<T = i32> = BstNodeLink<Interval<T>, (), MaxEnd<T>>;
+
+/**
+ * Interval tree (CLRS 14.3) on top of the plain BST: insertion is the regular
+ * `BstNode::find_slot` + `attach_with_meta`, deletion `BstNode::delete`, after which the max
+ * end points are refreshed on the path from the lowest changed node up to the root
+ */
+#[derive(Debug)]
+pub struct IntervalTree<T = i32> {
+    root: Option<IntervalNodeLink<T>>,
+    len: usize,
+}
+
+impl<T: Ord + Clone> IntervalTree<T> {
+    pub fn new() -> Self {
+        IntervalTree { root: None, len: 0 }
+    }
+
+    // Root of the tree, e.g. for generate_dotfile_bst
+    pub fn root(&self) -> Option<&IntervalNodeLink<T>> {
+        self.root.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Insert an interval, returns false if the very same interval is already stored
+    pub fn insert(&mut self, interval: Interval<T>) -> bool {
+        match BstNode::find_slot(&self.root, &interval) {
+            Slot::Occupied(_) => false,
+            Slot::Vacant(parent, is_left) => {
+                let end = MaxEnd(interval.end.clone());
+                BstNode::attach_with_meta(&mut self.root, parent.clone(), is_left, interval, (), end);
+                update_max_upward(parent);
+                self.len += 1;
+                true
+            }
+        }
+    }
+
+    // Remove an interval, returns false if it is not in the tree
+    pub fn remove(&mut self, interval: &Interval<T>) -> bool {
+        let z = match self.root.as_ref().and_then(|root| BstNode::tree_search(root, interval)) {
+            Some(node) => node,
+            None => return false,
+        };
+        let fixup_start = BstNode::delete_fixup_start(&z);
+        BstNode::delete(&mut self.root, &z);
+        update_max_upward(fixup_start);
+        self.len -= 1;
+        true
+    }
+
+    pub fn contains(&self, interval: &Interval<T>) -> bool {
+        self.root.as_ref().and_then(|root| BstNode::tree_search(root, interval)).is_some()
+    }
+
+    /**
+     * Any one stored interval overlapping query (CLRS INTERVAL-SEARCH)
+     * Go left whenever the left subtree reaches far enough to the right, otherwise go right
+     */
+    pub fn any_overlap(&self, query: &Interval<T>) -> Option<Interval<T>> {
+        let mut current = self.root.clone();
+        while let Some(node) = current {
+            let interval = node.borrow().key.clone().unwrap();
+            if interval.overlaps(query) {
+                return Some(interval);
+            }
+            let left = node.borrow().left.clone();
+            current = match left {
+                Some(left) if left.borrow().meta.0 >= query.start => Some(left),
+                _ => node.borrow().right.clone(),
+            };
+        }
+        None
+    }
+
+    /**
+     * Every stored interval overlapping query, sorted by start
+     * A left subtree is skipped when its max end is before the query start, and a right
+     * subtree is skipped when the node itself already starts after the query end
+     */
+    pub fn all_overlaps(&self, query: &Interval<T>) -> Vec<Interval<T>> {
+        let mut found = Vec::new();
+        if let Some(root) = &self.root {
+            collect_overlaps(root, query, &mut found);
+        }
+        found
+    }
+
+    // All intervals sorted by start
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Interval<T>> {
+        let nodes = self.root.as_ref().map(BstNode::in_order);
+        nodes.into_iter().flatten().map(|node| node.borrow().key.clone().unwrap())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+}
+
+impl<T: Ord + Clone> Default for IntervalTree<T> {
+    fn default() -> Self {
+        IntervalTree::new()
+    }
+}
+
+// In-order walk with an explicit stack, pruned like the recursive search so a degenerate tree cannot overflow
+fn collect_overlaps<T: Ord + Clone>(root: &IntervalNodeLink<T>, query: &Interval<T>, found: &mut Vec<Interval<T>>) {
+    let mut stack: Vec<IntervalNodeLink<T>> = Vec::new();
+    let mut current = Some(root.clone());
+    loop {
+        // a subtree whose max end is before the query start holds no overlaps
+        while let Some(node) = current.take() {
+            if node.borrow().meta.0 < query.start {
+                break;
+            }
+            current = node.borrow().left.clone();
+            stack.push(node);
+        }
+        let node = match stack.pop() {
+            Some(node) => node,
+            None => return,
+        };
+        let node_borrow = node.borrow();
+        let interval = node_borrow.key.as_ref().unwrap();
+        if interval.overlaps(query) {
+            found.push(interval.clone());
+        }
+        if interval.start <= query.end {
+            current = node_borrow.right.clone();
+        }
+    }
+}
+
+// Recompute the max end point of node from its own interval and its children
+fn update_max<T: Ord + Clone>(node: &IntervalNodeLink<T>) {
+    let mut max_end = node.borrow().key.as_ref().unwrap().end.clone();
+    for child in [node.borrow().left.clone(), node.borrow().right.clone()].into_iter().flatten() {
+        if child.borrow().meta.0 > max_end {
+            max_end = child.borrow().meta.0.clone();
+        }
+    }
+    node.borrow_mut().meta = MaxEnd(max_end);
+}
+
+fn update_max_upward<T: Ord + Clone>(node: Option<IntervalNodeLink<T>>) {
+    let mut current = node;
+    while let Some(exist) = current {
+        update_max(&exist);
+        current = BstNode::upgrade_weak_to_strong(exist.borrow().parent.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::test_util::TestRng;
+
+    // Recompute every max end point from scratch and compare with the stored one
+    fn check_max(node: &IntervalNodeLink) -> i32 {
+        let mut max_end = node.borrow().key.unwrap().end;
+        for child in [node.borrow().left.clone(), node.borrow().right.clone()].into_iter().flatten() {
+            max_end = max_end.max(check_max(&child));
+        }
+        assert_eq!(node.borrow().meta, MaxEnd(max_end));
+        max_end
+    }
+
+    fn random_intervals(count: usize, seed: u64) -> Vec<Interval<i32>> {
+        let mut rng = TestRng::new(seed);
+        (0..count)
+            .map(|_| {
+                let start = rng.below(1000) as i32;
+                Interval::new(start, start + rng.below(60) as i32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn overlap_queries_match_brute_force() {
+        let mut tree = IntervalTree::new();
+        let mut stored: Vec<Interval<i32>> = Vec::new();
+        for interval in random_intervals(300, 5) {
+            if tree.insert(interval) {
+                stored.push(interval);
+            }
+        }
+        check_max(tree.root().unwrap());
+        assert_eq!(tree.len(), stored.len());
+
+        // remove every third interval, the max end points must follow the deletes
+        for interval in stored.iter().step_by(3) {
+            assert!(tree.remove(interval));
+        }
+        stored = stored.into_iter().enumerate().filter(|(i, _)| i % 3 != 0).map(|(_, x)| x).collect();
+        stored.sort();
+        check_max(tree.root().unwrap());
+
+        for query in random_intervals(100, 17) {
+            let expected: Vec<Interval<i32>> = stored.iter().copied().filter(|x| x.overlaps(&query)).collect();
+            assert_eq!(tree.all_overlaps(&query), expected);
+            match tree.any_overlap(&query) {
+                Some(found) => assert!(found.overlaps(&query) && tree.contains(&found)),
+                None => assert!(expected.is_empty()),
+            }
+        }
+    }
+
+    #[test]
+    fn small_example() {
+        let mut tree: IntervalTree<i64> = IntervalTree::new();
+        for (start, end) in [(16, 21), (8, 9), (25, 30), (5, 8), (15, 23), (17, 19), (26, 26), (0, 3), (6, 10), (19, 20)] {
+            tree.insert(Interval::new(start, end));
+        }
+        assert!(!tree.insert(Interval::new(8, 9)));
+        assert_eq!(tree.root().unwrap().borrow().meta, MaxEnd(30));
+        assert_eq!(tree.any_overlap(&Interval::new(11, 14)), None);
+        assert_eq!(
+            tree.all_overlaps(&Interval::new(9, 16)),
+            vec![Interval::new(6, 10), Interval::new(8, 9), Interval::new(15, 23), Interval::new(16, 21)]
+        );
+        assert!(tree.remove(&Interval::new(25, 30)));
+        assert_eq!(tree.root().unwrap().borrow().meta, MaxEnd(26));
+        assert_eq!(tree.all_overlaps(&Interval::new(27, 40)), vec![]);
+
+        let dot_path = std::env::temp_dir().join("interval_graph_test.dot");
+        crate::tool::generate_dotfile_bst(tree.root().unwrap(), dot_path.to_str().unwrap());
+        let dot = std::fs::read_to_string(&dot_path).unwrap();
+        assert!(dot.contains("\t\"[16, 21]\"--\"[8, 9]\";\n"));
+    }
+
+    #[test]
+    fn endpoints_without_default() {
+        // Instant has no Default, the new leaf gets its MaxEnd when it is attached
+        let start = std::time::Instant::now();
+        let at = |secs: u64| start + std::time::Duration::from_secs(secs);
+        let mut tree: IntervalTree<std::time::Instant> = IntervalTree::default();
+        for (from, to) in [(10, 20), (0, 5), (30, 40), (15, 50)] {
+            assert!(tree.insert(Interval::new(at(from), at(to))));
+        }
+        assert_eq!(tree.root().unwrap().borrow().meta, MaxEnd(at(50)));
+        assert_eq!(tree.all_overlaps(&Interval::new(at(42), at(45))), vec![Interval::new(at(15), at(50))]);
+        assert!(tree.remove(&Interval::new(at(15), at(50))));
+        assert_eq!(tree.root().unwrap().borrow().meta, MaxEnd(at(40)));
+    }
+}
@@ -1,8 +1,23 @@
+pub mod arena;
+pub mod avl;
 pub mod bst;
+pub mod bst_map;
+pub mod cursor;
+pub mod interval;
+pub mod persistent;
+pub mod rbtree;
+pub mod scapegoat;
+pub mod splay;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub mod traversal;
+pub mod treap;
 pub mod tree {
     use std::cell::RefCell;
     use std::rc::{Rc, Weak};
 
+    use crate::structure::traversal::{InOrderIter, LevelOrderIter, PostOrderIter, PreOrderIter};
+
     pub type NodeLink = Rc<RefCell<Node>>;
     pub type WeakNodeLink = Weak<RefCell<Node>>;
 
@@ -25,6 +40,7 @@ pub mod tree {
             }
         }
 
+        #[allow(clippy::let_and_return)]
         pub fn new_nodelink(value: i32) -> NodeLink {
             let currentnode = Node::new(value);
             let currentlink = Rc::new(RefCell::new(currentnode));
@@ -47,6 +63,7 @@ pub mod tree {
         }
 
         //private interface
+        #[allow(clippy::let_and_return)]
         fn new_with_parent(parent: &NodeLink, value: i32) -> NodeLink {
             let mut currentnode = Node::new(value);
             currentnode.add_parent(Rc::<RefCell<Node>>::downgrade(parent));
@@ -54,6 +71,7 @@ pub mod tree {
             currentlink
         }
 
+        #[allow(clippy::let_and_return)]
         fn new_from_node(node: Node) -> NodeLink {
             let currentlink = Rc::new(RefCell::new(node));
             currentlink
@@ -79,6 +97,7 @@ pub mod tree {
         /**
          * As the name implied, used to upgrade parent node to strong nodelink
          */
+        #[allow(clippy::manual_map)]
         pub fn upgrade_weak_to_strong(node: Option<WeakNodeLink>) -> Option<NodeLink> {
             match node {
                 None => None,
@@ -102,6 +121,7 @@ pub mod tree {
         }
 
         //helper function
+        #[allow(clippy::needless_return)]
         fn is_node_match_both_weak(
             node1: Option<WeakNodeLink>,
             node2: Option<WeakNodeLink>,
@@ -115,6 +135,7 @@ pub mod tree {
         }
 
         //helper function to compare both nodelink
+        #[allow(clippy::needless_return)]
         fn is_node_match_both_strong(node1: Option<NodeLink>, node2: Option<NodeLink>) -> bool {
             if node1.is_none() && node2.is_none() {
                 return true;
@@ -129,6 +150,7 @@ pub mod tree {
          * This function will return the node that match value
          * Let's assume the tree won't have any value duplicates
          */
+        #[allow(clippy::needless_return)]
         pub fn get_node_by_value(&self, value: i32) -> Option<NodeLink>{
             //check current node value
             if self.value == value {
@@ -218,6 +240,7 @@ pub mod tree {
          * Count the amount of nodes in the whole subtree, in the current node
          * assume when enter the function the current node isn't a null
          */
+        #[allow(clippy::needless_return)]
         pub fn count_nodes(&self) -> i32 {
             let mut count = 0;
             let nodelink: Rc<RefCell<Node>> = Node::new_from_node(self.clone());
@@ -226,6 +249,7 @@ pub mod tree {
         }
 
         //the same as above except start the count from nodelink reference parameter
+        #[allow(clippy::needless_return, clippy::needless_borrow)]
         pub fn count_nodes_by_nodelink(node: &NodeLink, count: i32) -> i32 {
             let mut left_count: i32 = 0;
             let mut right_count: i32 = 0;
@@ -241,6 +265,7 @@ pub mod tree {
         /**Count depth of the tree in the current node
          * Count from root is started from 0
          */
+        #[allow(clippy::needless_return)]
         pub fn tree_depth(&self) -> i32 {
             let depth: i32 = 0;
             let nodelink: Rc<RefCell<Node>> = Node::new_from_node(self.clone());
@@ -248,6 +273,7 @@ pub mod tree {
         }
 
         //track depth by traversing all nodes but returned depth count per path. The highest number will be returned
+        #[allow(clippy::only_used_in_recursion)]
         fn track_depth(&self, node: &NodeLink, depth: i32) -> i32 {
             let mut left_depth: i32 = 0;
             let mut right_depth: i32 = 0;
@@ -266,6 +292,26 @@ pub mod tree {
             right_depth
         }
 
+        // In-order iterator over the subtree rooted at nodelink
+        pub fn in_order(nodelink: &NodeLink) -> InOrderIter<NodeLink> {
+            InOrderIter::new(Some(nodelink.clone()))
+        }
+
+        // Pre-order iterator over the subtree rooted at nodelink
+        pub fn pre_order(nodelink: &NodeLink) -> PreOrderIter<NodeLink> {
+            PreOrderIter::new(Some(nodelink.clone()))
+        }
+
+        // Post-order iterator over the subtree rooted at nodelink
+        pub fn post_order(nodelink: &NodeLink) -> PostOrderIter<NodeLink> {
+            PostOrderIter::new(Some(nodelink.clone()))
+        }
+
+        // Level-order (BFS) iterator over the subtree rooted at nodelink
+        pub fn level_order(nodelink: &NodeLink) -> LevelOrderIter<NodeLink> {
+            LevelOrderIter::new(Some(nodelink.clone()))
+        }
+
         /**
          * a node is guaranteed to have two childs at most, since this is a binary tree
          * a sibling is a node which has same direct parent
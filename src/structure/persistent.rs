@@ -0,0 +1,350 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+/**
+ * Immutable node of a `PersistentBst`, shared between versions through `Rc`
+ * There is no parent link: a node can sit in many versions at once, each with its own
+ * path from the root, so the walks that need the parent start from the root instead
+ */
+#[derive(Debug)]
+pub struct PersistentNode<K> {
+    pub key: K,
+    pub left: Option<Rc<PersistentNode<K>>>,
+    pub right: Option<Rc<PersistentNode<K>>>,
+}
+
+pub type PersistentNodeLink<K = i32> = Rc<PersistentNode<K>>;
+
+impl<K: Clone> PersistentNode<K> {
+    fn new_link(key: K, left: Option<PersistentNodeLink<K>>, right: Option<PersistentNodeLink<K>>) -> PersistentNodeLink<K> {
+        Rc::new(PersistentNode { key, left, right })
+    }
+
+    // Copy of this node with one child swapped, the other child is shared
+    fn with_child(&self, went_left: bool, child: Option<PersistentNodeLink<K>>) -> PersistentNodeLink<K> {
+        if went_left {
+            PersistentNode::new_link(self.key.clone(), child, self.right.clone())
+        } else {
+            PersistentNode::new_link(self.key.clone(), self.left.clone(), child)
+        }
+    }
+}
+
+// Same idea as the BstNode drop: unshared children are detached onto a stack first
+impl<K> Drop for PersistentNode<K> {
+    fn drop(&mut self) {
+        let mut stack: Vec<PersistentNodeLink<K>> = Vec::new();
+        stack.extend(self.left.take());
+        stack.extend(self.right.take());
+        while let Some(node) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+/**
+ * One version of a persistent (immutable) BST set
+ * `insert` and `remove` leave self untouched and return a new version: only the nodes on
+ * the search path are copied, every other subtree is shared with the previous version,
+ * so an update costs O(h) time and memory and all old versions stay queryable.
+ * Cloning a version is O(1). Keys are unique
+ */
+#[derive(Debug)]
+pub struct PersistentBst<K = i32> {
+    root: Option<PersistentNodeLink<K>>,
+    len: usize,
+}
+
+impl<K> Clone for PersistentBst<K> {
+    fn clone(&self) -> Self {
+        PersistentBst {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K: Ord + Clone> PersistentBst<K> {
+    pub fn new() -> Self {
+        PersistentBst { root: None, len: 0 }
+    }
+
+    pub fn root(&self) -> Option<&PersistentNodeLink<K>> {
+        self.root.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // New version with key added, or a clone of this version if key is already there
+    pub fn insert(&self, key: K) -> PersistentBst<K> {
+        let mut path = Vec::new();
+        let mut current = self.root.clone();
+        while let Some(node) = current {
+            let went_left = match key.cmp(&node.key) {
+                Ordering::Equal => return self.clone(),
+                Ordering::Less => true,
+                Ordering::Greater => false,
+            };
+            current = if went_left { node.left.clone() } else { node.right.clone() };
+            path.push((node, went_left));
+        }
+        let leaf = PersistentNode::new_link(key, None, None);
+        PersistentBst {
+            root: copy_path(path, Some(leaf)),
+            len: self.len + 1,
+        }
+    }
+
+    /**
+     * New version without key, or a clone of this version if key is missing
+     * A node with two children is replaced by a copy of its successor, and the successor
+     * is cut out of a copy of the right subtree's left spine, like BstNode::delete
+     */
+    pub fn remove(&self, key: &K) -> PersistentBst<K> {
+        let mut path = Vec::new();
+        let mut current = self.root.clone();
+        let z = loop {
+            let node = match current {
+                Some(node) => node,
+                None => return self.clone(),
+            };
+            let went_left = match key.cmp(&node.key) {
+                Ordering::Equal => break node,
+                Ordering::Less => true,
+                Ordering::Greater => false,
+            };
+            current = if went_left { node.left.clone() } else { node.right.clone() };
+            path.push((node, went_left));
+        };
+
+        let replacement = match (&z.left, &z.right) {
+            (None, right) => right.clone(),
+            (left, None) => left.clone(),
+            (Some(left), Some(right)) => {
+                // walk the left spine of the right subtree down to the successor
+                let mut spine = Vec::new();
+                let mut successor = right.clone();
+                while let Some(next) = successor.left.clone() {
+                    spine.push((successor, true));
+                    successor = next;
+                }
+                let new_right = copy_path(spine, successor.right.clone());
+                Some(PersistentNode::new_link(successor.key.clone(), Some(left.clone()), new_right))
+            }
+        };
+        PersistentBst {
+            root: copy_path(path, replacement),
+            len: self.len - 1,
+        }
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.search(key).is_some()
+    }
+
+    // Node holding key in this version
+    pub fn search(&self, key: &K) -> Option<&PersistentNodeLink<K>> {
+        let mut current = self.root.as_ref();
+        while let Some(node) = current {
+            current = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node.left.as_ref(),
+                Ordering::Greater => node.right.as_ref(),
+            };
+        }
+        None
+    }
+
+    pub fn min(&self) -> Option<&K> {
+        let mut current = self.root.as_ref()?;
+        while let Some(left) = &current.left {
+            current = left;
+        }
+        Some(&current.key)
+    }
+
+    pub fn max(&self) -> Option<&K> {
+        let mut current = self.root.as_ref()?;
+        while let Some(right) = &current.right {
+            current = right;
+        }
+        Some(&current.key)
+    }
+
+    /**
+     * Smallest key greater than key, which does not have to be in the tree
+     * Without parent links this descends from the root and remembers the last node
+     * where the walk turned left, O(h) like tree_successor
+     */
+    pub fn successor(&self, key: &K) -> Option<&K> {
+        let mut candidate = None;
+        let mut current = self.root.as_ref();
+        while let Some(node) = current {
+            if node.key > *key {
+                candidate = Some(&node.key);
+                current = node.left.as_ref();
+            } else {
+                current = node.right.as_ref();
+            }
+        }
+        candidate
+    }
+
+    // Largest key smaller than key, the mirror of successor
+    pub fn predecessor(&self, key: &K) -> Option<&K> {
+        let mut candidate = None;
+        let mut current = self.root.as_ref();
+        while let Some(node) = current {
+            if node.key < *key {
+                candidate = Some(&node.key);
+                current = node.right.as_ref();
+            } else {
+                current = node.left.as_ref();
+            }
+        }
+        candidate
+    }
+
+    // All keys of this version in order
+    pub fn iter(&self) -> PersistentIter<'_, K> {
+        let mut iter = PersistentIter { stack: Vec::new() };
+        iter.push_left_spine(self.root.as_ref());
+        iter
+    }
+}
+
+impl<K: Ord + Clone> Default for PersistentBst<K> {
+    fn default() -> Self {
+        PersistentBst::new()
+    }
+}
+
+/**
+ * Rebuild the search path bottom-up on top of child: every node on the path is copied
+ * with the child it went to replaced by the copy below it. Returns the new root
+ */
+fn copy_path<K: Clone>(path: Vec<(PersistentNodeLink<K>, bool)>, child: Option<PersistentNodeLink<K>>) -> Option<PersistentNodeLink<K>> {
+    let mut child = child;
+    for (node, went_left) in path.into_iter().rev() {
+        child = Some(node.with_child(went_left, child));
+    }
+    child
+}
+
+// In-order iterator returned by `PersistentBst::iter`, it keeps the pending left spine on a stack
+pub struct PersistentIter<'a, K> {
+    stack: Vec<&'a PersistentNodeLink<K>>,
+}
+
+impl<'a, K> PersistentIter<'a, K> {
+    fn push_left_spine(&mut self, node: Option<&'a PersistentNodeLink<K>>) {
+        let mut current = node;
+        while let Some(node) = current {
+            self.stack.push(node);
+            current = node.left.as_ref();
+        }
+    }
+}
+
+impl<'a, K> Iterator for PersistentIter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(node.right.as_ref());
+        Some(&node.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(version: &PersistentBst) -> Vec<i32> {
+        version.iter().copied().collect()
+    }
+
+    #[test]
+    fn old_versions_stay_queryable() {
+        let mut versions = vec![PersistentBst::new()];
+        for key in [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9] {
+            let next = versions.last().unwrap().insert(key);
+            versions.push(next);
+        }
+        let full = versions.last().unwrap().clone();
+        let without_6 = full.remove(&6);
+        let without_15 = without_6.remove(&15);
+
+        assert_eq!(keys(&versions[3]), vec![6, 15, 18]);
+        assert_eq!(keys(&full), vec![2, 3, 4, 6, 7, 9, 13, 15, 17, 18, 20]);
+        assert_eq!(keys(&without_6), vec![2, 3, 4, 7, 9, 13, 15, 17, 18, 20]);
+        assert_eq!(keys(&without_15), vec![2, 3, 4, 7, 9, 13, 17, 18, 20]);
+        assert!(full.contains(&6) && !without_6.contains(&6));
+        assert_eq!((full.len(), without_15.len()), (11, 9));
+        assert_eq!(without_15.root().unwrap().key, 17);
+        // removing or inserting nothing returns the same version
+        assert!(Rc::ptr_eq(full.remove(&100).root().unwrap(), full.root().unwrap()));
+        assert!(Rc::ptr_eq(full.insert(7).root().unwrap(), full.root().unwrap()));
+    }
+
+    #[test]
+    fn updates_share_untouched_subtrees() {
+        let mut version = PersistentBst::new();
+        for key in [50, 30, 70, 20, 40, 60, 80] {
+            version = version.insert(key);
+        }
+        let next = version.insert(65);
+        let old_root = version.root().unwrap();
+        let new_root = next.root().unwrap();
+        assert!(!Rc::ptr_eq(old_root, new_root));
+        // 65 goes right, so the whole left subtree and 80 are shared
+        assert!(Rc::ptr_eq(old_root.left.as_ref().unwrap(), new_root.left.as_ref().unwrap()));
+        let (old_70, new_70) = (old_root.right.as_ref().unwrap(), new_root.right.as_ref().unwrap());
+        assert!(Rc::ptr_eq(old_70.right.as_ref().unwrap(), new_70.right.as_ref().unwrap()));
+        assert!(version.search(&65).is_none() && next.search(&65).is_some());
+    }
+
+    #[test]
+    fn successor_and_predecessor_by_descent() {
+        let mut version = PersistentBst::new();
+        for key in [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9] {
+            version = version.insert(key);
+        }
+        assert_eq!(version.successor(&13), Some(&15));
+        assert_eq!(version.successor(&9), Some(&13));
+        assert_eq!(version.successor(&14), Some(&15));
+        assert_eq!(version.successor(&20), None);
+        assert_eq!(version.predecessor(&17), Some(&15));
+        assert_eq!(version.predecessor(&2), None);
+        assert_eq!((version.min(), version.max()), (Some(&2), Some(&20)));
+        let older = version.remove(&15);
+        assert_eq!(older.successor(&13), Some(&17));
+        assert_eq!(version.successor(&13), Some(&15));
+    }
+
+    #[test]
+    fn deep_version_drops_without_recursion() {
+        let mut version = PersistentBst::new();
+        let mut root = None;
+        // a right spine built directly, inserting sorted keys one by one would be quadratic
+        for key in (0..200_000).rev() {
+            root = Some(PersistentNode::new_link(key, None, root));
+        }
+        version.root = root;
+        version.len = 200_000;
+        assert_eq!(version.max(), Some(&199_999));
+        let next = version.insert(-1);
+        drop(version);
+        assert_eq!(next.len(), 200_001);
+        assert_eq!(next.min(), Some(&-1));
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::tool::DotNodeStyle;
+
+// Node color, new nodes start red
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Color {
+    #[default]
+    Red,
+    Black,
+}
+
+impl DotNodeStyle for Color {
+    fn dot_style(&self) -> Option<&'static str> {
+        match self {
+            Color::Red => Some("color=red, fontcolor=red"),
+            Color::Black => None,
+        }
+    }
+}
+
+/**
+ * Red-black nodes are regular `BstNode`s whose metadata is the node color,
+ * missing children count as black leaves
+ */
+pub type RbNodeLink<K = i32> = BstNodeLink<K, (), Color>;
+
+/**
+ * Red-black tree following CLRS: the structural part of insert and delete is the plain
+ * BST one (`BstNode::insert_unique`, `BstNode::delete` and its `transplant`), followed by
+ * the color fixups. Keys are unique, inserting a key that is already present is rejected
+ */
+#[derive(Debug)]
+pub struct RbTree<K = i32> {
+    root: Option<RbNodeLink<K>>,
+    len: usize,
+}
+
+impl<K: Ord> RbTree<K> {
+    pub fn new() -> Self {
+        RbTree { root: None, len: 0 }
+    }
+
+    // Root of the tree, e.g. for tree_search or generate_dotfile_bst
+    pub fn root(&self) -> Option<&RbNodeLink<K>> {
+        self.root.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Insert a key as a red leaf and repair the red-child rule on the way up
+     * Returns false if the key was already in the tree
+     */
+    pub fn insert(&mut self, key: K) -> bool {
+        match BstNode::insert_unique(&mut self.root, key, ()) {
+            Ok(new_node) => {
+                insert_fixup(&mut self.root, new_node);
+                self.len += 1;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /**
+     * Remove a key, returns false if the key is not in the tree
+     * Before handing the node to `BstNode::delete` we record, as CLRS does, the node y that
+     * actually leaves its position, its original color, and the node x that moves into
+     * y's old place together with x's new parent (x itself may be a missing leaf)
+     */
+    pub fn remove(&mut self, key: &K) -> bool {
+        let z = match self.search(key) {
+            Some(node) => node,
+            None => return false,
+        };
+        let z_left = z.borrow().left.clone();
+        let z_right = z.borrow().right.clone();
+
+        let (y, y_original_color, x, x_parent) = match (&z_left, &z_right) {
+            (None, _) => (z.clone(), z.borrow().meta, z_right.clone(), parent_of(&z)),
+            (_, None) => (z.clone(), z.borrow().meta, z_left.clone(), parent_of(&z)),
+            (Some(_), Some(right)) => {
+                let y = BstNode::minimum(right);
+                let y_parent = parent_of(&y).unwrap();
+                let x_parent = if Rc::ptr_eq(&y_parent, &z) { y.clone() } else { y_parent };
+                let x = y.borrow().right.clone();
+                let y_color = y.borrow().meta;
+                (y, y_color, x, Some(x_parent))
+            }
+        };
+
+        BstNode::delete(&mut self.root, &z);
+        if !Rc::ptr_eq(&y, &z) {
+            // y took z's place, so it also takes z's color
+            y.borrow_mut().meta = z.borrow().meta;
+        }
+        if y_original_color == Color::Black {
+            delete_fixup(&mut self.root, x, x_parent);
+        }
+        self.len -= 1;
+        true
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.search(key).is_some()
+    }
+
+    // Live node holding key, ready for tree_successor
+    pub fn search(&self, key: &K) -> Option<RbNodeLink<K>> {
+        BstNode::tree_search(self.root.as_ref()?, key)
+    }
+
+    // All keys in order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = K>
+    where
+        K: Clone,
+    {
+        let nodes = self.root.as_ref().map(BstNode::in_order);
+        nodes.into_iter().flatten().map(|node| node.borrow().key.clone().unwrap())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /**
+     * Check the red-black properties: the root is black, a red node has no red child,
+     * and every path from a node down to a missing leaf has the same number of black nodes.
+     * Also checks the key order and the parent links, returns the black height of the tree
+     */
+    pub fn validate(&self) -> Result<usize, String>
+    where
+        K: Debug,
+    {
+        match &self.root {
+            None => Ok(0),
+            Some(root) => {
+                if root.borrow().meta == Color::Red {
+                    return Err("root is red".to_string());
+                }
+                if root.borrow().parent.is_some() {
+                    return Err("root has a parent link".to_string());
+                }
+                validate_subtree(root)
+            }
+        }
+    }
+}
+
+impl<K: Ord> Default for RbTree<K> {
+    fn default() -> Self {
+        RbTree::new()
+    }
+}
+
+// Black height of the subtree at node, counting the missing leaves as black
+fn validate_subtree<K: Ord + Debug>(node: &RbNodeLink<K>) -> Result<usize, String> {
+    let node_borrow = node.borrow();
+    let key = node_borrow.key.as_ref().unwrap();
+    let mut black_heights = [1, 1];
+    for (i, child) in [&node_borrow.left, &node_borrow.right].into_iter().enumerate() {
+        if let Some(child) = child {
+            if !parent_of(child).is_some_and(|parent| Rc::ptr_eq(&parent, node)) {
+                return Err(format!("broken parent link below {:?}", key));
+            }
+            let in_order = child.borrow().key.as_ref().is_some_and(|k| (i == 0 && k < key) || (i == 1 && k > key));
+            if !in_order {
+                return Err(format!("keys out of order below {:?}", key));
+            }
+            if node_borrow.meta == Color::Red && child.borrow().meta == Color::Red {
+                return Err(format!("red node {:?} has a red child", key));
+            }
+            black_heights[i] = validate_subtree(child)?;
+        }
+    }
+    if black_heights[0] != black_heights[1] {
+        return Err(format!("black height differs below {:?}", key));
+    }
+    Ok(black_heights[0] + (node_borrow.meta == Color::Black) as usize)
+}
+
+// Color of an optional node, a missing leaf is black
+fn color_of<K>(node: &Option<RbNodeLink<K>>) -> Color {
+    match node {
+        Some(exist) => exist.borrow().meta,
+        None => Color::Black,
+    }
+}
+
+fn set_color<K>(node: &Option<RbNodeLink<K>>, color: Color) {
+    if let Some(exist) = node {
+        exist.borrow_mut().meta = color;
+    }
+}
+
+fn parent_of<K: Ord>(node: &RbNodeLink<K>) -> Option<RbNodeLink<K>> {
+    BstNode::upgrade_weak_to_strong(node.borrow().parent.clone())
+}
+
+fn is_left_child<K>(parent: &RbNodeLink<K>, node: &RbNodeLink<K>) -> bool {
+    parent.borrow().left.as_ref().is_some_and(|left| Rc::ptr_eq(left, node))
+}
+
+// CLRS RB-INSERT-FIXUP, z is the freshly inserted red node
+fn insert_fixup<K: Ord>(root: &mut Option<RbNodeLink<K>>, z: RbNodeLink<K>) {
+    let mut z = z;
+    while let Some(parent) = parent_of(&z).filter(|p| p.borrow().meta == Color::Red) {
+        // a red parent is never the root, so the grandparent exists
+        let grandparent = parent_of(&parent).unwrap();
+        let parent_is_left = is_left_child(&grandparent, &parent);
+        let uncle = if parent_is_left {
+            grandparent.borrow().right.clone()
+        } else {
+            grandparent.borrow().left.clone()
+        };
+
+        if color_of(&uncle) == Color::Red {
+            parent.borrow_mut().meta = Color::Black;
+            set_color(&uncle, Color::Black);
+            grandparent.borrow_mut().meta = Color::Red;
+            z = grandparent;
+            continue;
+        }
+
+        let mut parent = parent;
+        if parent_is_left {
+            if !is_left_child(&parent, &z) {
+                z = parent;
+                BstNode::rotate_left(root, &z);
+                parent = parent_of(&z).unwrap();
+            }
+            parent.borrow_mut().meta = Color::Black;
+            grandparent.borrow_mut().meta = Color::Red;
+            BstNode::rotate_right(root, &grandparent);
+        } else {
+            if is_left_child(&parent, &z) {
+                z = parent;
+                BstNode::rotate_right(root, &z);
+                parent = parent_of(&z).unwrap();
+            }
+            parent.borrow_mut().meta = Color::Black;
+            grandparent.borrow_mut().meta = Color::Red;
+            BstNode::rotate_left(root, &grandparent);
+        }
+    }
+    set_color(root, Color::Black);
+}
+
+/**
+ * CLRS RB-DELETE-FIXUP, x carries an extra black. Without a sentinel nil node,
+ * x may be a missing leaf, so its parent is tracked separately
+ */
+fn delete_fixup<K: Ord>(
+    root: &mut Option<RbNodeLink<K>>,
+    x: Option<RbNodeLink<K>>,
+    x_parent: Option<RbNodeLink<K>>,
+) {
+    let mut x = x;
+    let mut x_parent = x_parent;
+    while color_of(&x) == Color::Black {
+        let parent = match x_parent.clone() {
+            Some(parent) => parent,
+            None => break, // x is the root
+        };
+        let x_is_left = match &x {
+            Some(node) => is_left_child(&parent, node),
+            None => parent.borrow().left.is_none(),
+        };
+
+        if x_is_left {
+            // x carries an extra black, so its sibling w is a real node
+            let mut w = parent.borrow().right.clone().unwrap();
+            if w.borrow().meta == Color::Red {
+                w.borrow_mut().meta = Color::Black;
+                parent.borrow_mut().meta = Color::Red;
+                BstNode::rotate_left(root, &parent);
+                w = parent.borrow().right.clone().unwrap();
+            }
+            if color_of(&w.borrow().left) == Color::Black && color_of(&w.borrow().right) == Color::Black {
+                w.borrow_mut().meta = Color::Red;
+                x_parent = parent_of(&parent);
+                x = Some(parent);
+            } else {
+                if color_of(&w.borrow().right) == Color::Black {
+                    set_color(&w.borrow().left, Color::Black);
+                    w.borrow_mut().meta = Color::Red;
+                    BstNode::rotate_right(root, &w);
+                    w = parent.borrow().right.clone().unwrap();
+                }
+                w.borrow_mut().meta = parent.borrow().meta;
+                parent.borrow_mut().meta = Color::Black;
+                set_color(&w.borrow().right, Color::Black);
+                BstNode::rotate_left(root, &parent);
+                x = root.clone();
+                x_parent = None;
+            }
+        } else {
+            let mut w = parent.borrow().left.clone().unwrap();
+            if w.borrow().meta == Color::Red {
+                w.borrow_mut().meta = Color::Black;
+                parent.borrow_mut().meta = Color::Red;
+                BstNode::rotate_right(root, &parent);
+                w = parent.borrow().left.clone().unwrap();
+            }
+            if color_of(&w.borrow().right) == Color::Black && color_of(&w.borrow().left) == Color::Black {
+                w.borrow_mut().meta = Color::Red;
+                x_parent = parent_of(&parent);
+                x = Some(parent);
+            } else {
+                if color_of(&w.borrow().left) == Color::Black {
+                    set_color(&w.borrow().right, Color::Black);
+                    w.borrow_mut().meta = Color::Red;
+                    BstNode::rotate_left(root, &w);
+                    w = parent.borrow().left.clone().unwrap();
+                }
+                w.borrow_mut().meta = parent.borrow().meta;
+                parent.borrow_mut().meta = Color::Black;
+                set_color(&w.borrow().left, Color::Black);
+                BstNode::rotate_right(root, &parent);
+                x = root.clone();
+                x_parent = None;
+            }
+        }
+    }
+    set_color(&x, Color::Black);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::test_util::shuffled_keys;
+    use crate::tool::generate_dotfile_bst;
+
+    #[test]
+    fn sorted_insert_keeps_properties() {
+        let mut tree = RbTree::new();
+        for key in 0..1000 {
+            assert!(tree.insert(key));
+            if key % 50 == 0 {
+                tree.validate().unwrap();
+            }
+        }
+        assert!(tree.validate().unwrap() >= 5);
+        assert!(!tree.insert(10));
+        assert_eq!(tree.iter().collect::<Vec<_>>(), (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn random_insert_and_remove_keep_properties() {
+        let mut tree = RbTree::new();
+        for key in shuffled_keys(400, 3) {
+            tree.insert(key);
+        }
+        tree.validate().unwrap();
+        for (i, key) in shuffled_keys(400, 11).into_iter().enumerate() {
+            assert!(tree.remove(&key), "removing {}", key);
+            assert!(!tree.contains(&key));
+            if let Err(message) = tree.validate() {
+                panic!("after removing {}: {}", key, message);
+            }
+            assert_eq!(tree.len(), 399 - i);
+            assert_eq!(BstNode::subtree_size(&tree.root().cloned()), tree.len());
+        }
+        assert!(tree.root().is_none());
+    }
+
+    #[test]
+    fn validator_reports_violations() {
+        let mut tree = RbTree::new();
+        for key in [2, 1, 3] {
+            tree.insert(key);
+        }
+        assert_eq!(tree.validate(), Ok(2));
+        let left = tree.root().unwrap().borrow().left.clone();
+        set_color(&left, Color::Black);
+        assert!(tree.validate().is_err());
+        tree.root().unwrap().borrow_mut().meta = Color::Red;
+        assert_eq!(tree.validate(), Err("root is red".to_string()));
+    }
+
+    #[test]
+    fn dotfile_marks_red_nodes() {
+        let mut tree = RbTree::new();
+        for key in [2, 1, 3] {
+            tree.insert(key);
+        }
+        let dot_path = std::env::temp_dir().join("rbtree_graph_test.dot");
+        generate_dotfile_bst(tree.root().unwrap(), dot_path.to_str().unwrap());
+        let dot = std::fs::read_to_string(&dot_path).unwrap();
+        assert!(dot.contains("\t1 [color=red, fontcolor=red];\n"));
+        assert!(dot.contains("\t3 [color=red, fontcolor=red];\n"));
+        assert!(!dot.contains("\t2 ["));
+    }
+}
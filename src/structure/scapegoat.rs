@@ -0,0 +1,240 @@
+use std::rc::Rc;
+
+use crate::structure::bst::{BstNode, BstNodeLink};
+
+// Scapegoat trees keep no metadata of their own, they only rely on the subtree sizes
+pub type ScapegoatNodeLink<K = i32> = BstNodeLink<K, (), ()>;
+
+// Balance parameter used by `ScapegoatTree::new`
+pub const DEFAULT_ALPHA: f64 = 0.7;
+
+/**
+ * Scapegoat tree (Galperin and Rivest): a plain BST that only tracks its size and the
+ * largest size since the last full rebuild. An insert that lands deeper than
+ * log_{1/alpha}(len) walks up the parent links to the first ancestor whose child holds
+ * more than alpha of its subtree and rebuilds that ancestor perfectly balanced, a remove
+ * that shrinks the tree below alpha * max_len rebuilds the whole tree. Keys are unique
+ */
+#[derive(Debug)]
+pub struct ScapegoatTree<K = i32> {
+    root: Option<ScapegoatNodeLink<K>>,
+    len: usize,
+    max_len: usize,
+    alpha: f64,
+}
+
+impl<K: Ord> ScapegoatTree<K> {
+    pub fn new() -> Self {
+        ScapegoatTree::with_alpha(DEFAULT_ALPHA)
+    }
+
+    /**
+     * Empty tree with a custom alpha, lower is stricter: 0.5 keeps the tree almost
+     * perfectly balanced with frequent rebuilds, values near 1 rebuild rarely
+     * Panics unless 0.5 <= alpha < 1
+     */
+    pub fn with_alpha(alpha: f64) -> Self {
+        assert!((0.5..1.0).contains(&alpha), "alpha must be in [0.5, 1)");
+        ScapegoatTree {
+            root: None,
+            len: 0,
+            max_len: 0,
+            alpha,
+        }
+    }
+
+    // Root of the tree, e.g. for tree_search or generate_dotfile_bst
+    pub fn root(&self) -> Option<&ScapegoatNodeLink<K>> {
+        self.root.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    // Insert a key, returns false if it was already in the tree
+    pub fn insert(&mut self, key: K) -> bool {
+        let new_node = match BstNode::insert_unique(&mut self.root, key, ()) {
+            Ok(new_node) => new_node,
+            Err(_) => return false,
+        };
+        self.len += 1;
+        self.max_len = self.max_len.max(self.len);
+
+        if BstNode::depth(&new_node) as f64 > self.height_bound() {
+            let scapegoat = self.find_scapegoat(&new_node);
+            rebuild(&mut self.root, &scapegoat);
+        }
+        true
+    }
+
+    // Remove a key, returns false if it is not in the tree
+    pub fn remove(&mut self, key: &K) -> bool {
+        let z = match self.search(key) {
+            Some(node) => node,
+            None => return false,
+        };
+        BstNode::delete(&mut self.root, &z);
+        self.len -= 1;
+
+        if (self.len as f64) < self.alpha * self.max_len as f64 {
+            if let Some(root) = self.root.clone() {
+                rebuild(&mut self.root, &root);
+            }
+            self.max_len = self.len;
+        }
+        true
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.search(key).is_some()
+    }
+
+    // Live node holding key, ready for tree_successor
+    pub fn search(&self, key: &K) -> Option<ScapegoatNodeLink<K>> {
+        BstNode::tree_search(self.root.as_ref()?, key)
+    }
+
+    // All keys in order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = K>
+    where
+        K: Clone,
+    {
+        let nodes = self.root.as_ref().map(BstNode::in_order);
+        nodes.into_iter().flatten().map(|node| node.borrow().key.clone().unwrap())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+        self.max_len = 0;
+    }
+
+    // log_{1/alpha}(len), the deepest an insert may land without triggering a rebuild
+    fn height_bound(&self) -> f64 {
+        (self.len as f64).ln() / (1.0 / self.alpha).ln()
+    }
+
+    /**
+     * Walk up from the new leaf to the first ancestor whose child is too heavy,
+     * i.e. size(child) > alpha * size(ancestor). The leaf is too deep, so such an
+     * ancestor always exists
+     */
+    fn find_scapegoat(&self, node: &ScapegoatNodeLink<K>) -> ScapegoatNodeLink<K> {
+        let mut child = node.clone();
+        loop {
+            let parent = BstNode::upgrade_weak_to_strong(child.borrow().parent.clone());
+            let parent = match parent {
+                Some(parent) => parent,
+                None => return child,
+            };
+            if child.borrow().size as f64 > self.alpha * parent.borrow().size as f64 {
+                return parent;
+            }
+            child = parent;
+        }
+    }
+}
+
+impl<K: Ord> Default for ScapegoatTree<K> {
+    fn default() -> Self {
+        ScapegoatTree::new()
+    }
+}
+
+/**
+ * Rebuild the subtree rooted at node perfectly balanced, reusing its nodes
+ * The nodes are collected in order and relinked by `BstNode::link_balanced`, then the new
+ * subtree root takes the place of node under the old parent
+ */
+fn rebuild<K: Ord>(root: &mut Option<ScapegoatNodeLink<K>>, node: &ScapegoatNodeLink<K>) {
+    let parent = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
+    let was_left = match &parent {
+        Some(parent) => parent.borrow().left.as_ref().is_some_and(|left| Rc::ptr_eq(left, node)),
+        None => false,
+    };
+    let nodes: Vec<ScapegoatNodeLink<K>> = BstNode::in_order(node).collect();
+    let new_subtree = BstNode::link_balanced(&nodes).unwrap();
+    match parent {
+        Some(parent) => {
+            new_subtree.borrow_mut().parent = Some(Rc::downgrade(&parent));
+            if was_left {
+                parent.borrow_mut().left = Some(new_subtree);
+            } else {
+                parent.borrow_mut().right = Some(new_subtree);
+            }
+        }
+        None => {
+            new_subtree.borrow_mut().parent = None;
+            *root = Some(new_subtree);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::test_util::check_bst;
+
+    // Height of the tree after checking it, within the scapegoat bound
+    fn check(tree: &ScapegoatTree) -> i32 {
+        let height = check_bst(tree.root());
+        assert_eq!(tree.iter().count(), tree.len());
+        if tree.len() > 1 {
+            let bound = (tree.len() as f64).ln() / (1.0 / tree.alpha()).ln();
+            assert!(height as f64 <= bound.floor() + 1.0, "height {} over bound {}", height, bound);
+        }
+        height
+    }
+
+    #[test]
+    fn sorted_insert_triggers_rebuilds() {
+        let mut tree = ScapegoatTree::new();
+        for key in 0..1000 {
+            assert!(tree.insert(key));
+        }
+        assert!(!tree.insert(500));
+        assert!(check(&tree) < 20);
+        assert_eq!(tree.iter().collect::<Vec<_>>(), (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn lower_alpha_keeps_tree_flatter() {
+        let mut strict = ScapegoatTree::with_alpha(0.55);
+        let mut loose = ScapegoatTree::with_alpha(0.9);
+        for key in 0..1000 {
+            strict.insert(key);
+            loose.insert(key);
+        }
+        assert!(check(&strict) < check(&loose));
+    }
+
+    #[test]
+    fn removals_rebuild_whole_tree() {
+        let mut tree = ScapegoatTree::new();
+        for key in 0..500 {
+            tree.insert((key * 7) % 500);
+        }
+        for key in 0..450 {
+            assert!(tree.remove(&key));
+            assert!(!tree.contains(&key));
+        }
+        check(&tree);
+        assert_eq!(tree.len(), 50);
+        assert!(!tree.remove(&0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn alpha_out_of_range() {
+        ScapegoatTree::<i32>::with_alpha(0.4);
+    }
+}
@@ -0,0 +1,276 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::structure::bst::{BstNode, BstNodeLink};
+
+// Splay trees keep no per-node metadata, the shape alone carries the access history
+pub type SplayNodeLink<K = i32> = BstNodeLink<K, (), ()>;
+
+/**
+ * Work counters of a splay tree, one operation is one search, insert or remove
+ * Nodes visited counts the descent, rotations count the splay that follows it
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SplayStats {
+    pub operations: u64,
+    pub nodes_visited: u64,
+    pub rotations: u64,
+}
+
+impl SplayStats {
+    // Average nodes visited plus rotations per operation, 0 before the first operation
+    pub fn amortized_cost(&self) -> f64 {
+        if self.operations == 0 {
+            return 0.0;
+        }
+        (self.nodes_visited + self.rotations) as f64 / self.operations as f64
+    }
+}
+
+/**
+ * Self-adjusting BST: every search, insert and remove splays the accessed node (or the
+ * last node on the search path when the key is missing) to the root by rotating it up
+ * through the parent links, so hot keys end up near the top. Keys are unique
+ */
+#[derive(Debug)]
+pub struct SplayTree<K = i32> {
+    root: Option<SplayNodeLink<K>>,
+    len: usize,
+    stats: SplayStats,
+}
+
+impl<K: Ord> SplayTree<K> {
+    pub fn new() -> Self {
+        SplayTree {
+            root: None,
+            len: 0,
+            stats: SplayStats::default(),
+        }
+    }
+
+    // Root of the tree, the most recently accessed node
+    pub fn root(&self) -> Option<&SplayNodeLink<K>> {
+        self.root.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn stats(&self) -> SplayStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = SplayStats::default();
+    }
+
+    /**
+     * Search for key and splay the node found, or the last node on the path if the key
+     * is missing, to the root. Takes &mut self because the shape changes
+     */
+    pub fn search(&mut self, key: &K) -> Option<SplayNodeLink<K>> {
+        self.stats.operations += 1;
+        match self.access(key) {
+            Some((node, true)) => Some(node),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&mut self, key: &K) -> bool {
+        self.search(key).is_some()
+    }
+
+    // Insert a key and splay it to the root, returns false if it was already in the tree
+    pub fn insert(&mut self, key: K) -> bool {
+        self.stats.operations += 1;
+        let (node, inserted) = match BstNode::insert_unique(&mut self.root, key, ()) {
+            Ok(new_node) => (new_node, true),
+            Err((existing, _)) => (existing, false),
+        };
+        // insert_unique compared the key against every ancestor of node
+        self.stats.nodes_visited += BstNode::depth(&node) as u64 + 1;
+        self.splay(&node);
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    /**
+     * Remove a key, returns false if it is not in the tree
+     * The node is splayed to the root first and then unlinked with `BstNode::delete`,
+     * a missing key still splays the last node on its search path
+     */
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.stats.operations += 1;
+        let z = match self.access(key) {
+            Some((node, true)) => node,
+            _ => return false,
+        };
+        BstNode::delete(&mut self.root, &z);
+        self.len -= 1;
+        true
+    }
+
+    // All keys in order, does not splay
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = K>
+    where
+        K: Clone,
+    {
+        let nodes = self.root.as_ref().map(BstNode::in_order);
+        nodes.into_iter().flatten().map(|node| node.borrow().key.clone().unwrap())
+    }
+
+    // Drop every node, the counters are kept
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /**
+     * Walk down looking for key, counting visited nodes, and splay the node where the
+     * walk ended. Returns that node and whether it holds key, None on an empty tree
+     */
+    fn access(&mut self, key: &K) -> Option<(SplayNodeLink<K>, bool)> {
+        let mut last = None;
+        let mut current = self.root.clone();
+        while let Some(node) = current {
+            self.stats.nodes_visited += 1;
+            let ordering = key.cmp(node.borrow().key.as_ref().unwrap());
+            let next = match ordering {
+                Ordering::Equal => {
+                    self.splay(&node);
+                    return Some((node, true));
+                }
+                Ordering::Less => node.borrow().left.clone(),
+                Ordering::Greater => node.borrow().right.clone(),
+            };
+            last = Some(node);
+            current = next;
+        }
+        let last = last?;
+        self.splay(&last);
+        Some((last, false))
+    }
+
+    // Rotate node up until it is the root, using zig, zig-zig and zig-zag steps
+    fn splay(&mut self, node: &SplayNodeLink<K>) {
+        while let Some(parent) = parent_of(node) {
+            let node_is_left = is_left_child(node, &parent);
+            match parent_of(&parent) {
+                None => self.rotate_up(node_is_left, &parent),
+                Some(grandparent) => {
+                    let parent_is_left = is_left_child(&parent, &grandparent);
+                    if node_is_left == parent_is_left {
+                        self.rotate_up(parent_is_left, &grandparent);
+                        self.rotate_up(node_is_left, &parent);
+                    } else {
+                        self.rotate_up(node_is_left, &parent);
+                        self.rotate_up(parent_is_left, &grandparent);
+                    }
+                }
+            }
+        }
+    }
+
+    // Rotate parent down so that its left (or right) child takes its place
+    fn rotate_up(&mut self, child_is_left: bool, parent: &SplayNodeLink<K>) {
+        if child_is_left {
+            BstNode::rotate_right(&mut self.root, parent);
+        } else {
+            BstNode::rotate_left(&mut self.root, parent);
+        }
+        self.stats.rotations += 1;
+    }
+}
+
+impl<K: Ord> Default for SplayTree<K> {
+    fn default() -> Self {
+        SplayTree::new()
+    }
+}
+
+fn parent_of<K: Ord>(node: &SplayNodeLink<K>) -> Option<SplayNodeLink<K>> {
+    BstNode::upgrade_weak_to_strong(node.borrow().parent.clone())
+}
+
+fn is_left_child<K>(node: &SplayNodeLink<K>, parent: &SplayNodeLink<K>) -> bool {
+    match &parent.borrow().left {
+        Some(left) => Rc::ptr_eq(left, node),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::bst::Bst;
+    use crate::structure::test_util::check_bst;
+
+    #[test]
+    fn accessed_key_becomes_root() {
+        let mut tree = SplayTree::new();
+        for key in [50, 30, 70, 20, 40, 60, 80, 10] {
+            assert!(tree.insert(key));
+            assert_eq!(tree.root().unwrap().borrow().key, Some(key));
+        }
+        assert!(!tree.insert(40));
+        assert_eq!(tree.root().unwrap().borrow().key, Some(40));
+
+        assert!(tree.contains(&10));
+        assert_eq!(tree.root().unwrap().borrow().key, Some(10));
+        // a miss splays the last node on the path
+        assert!(!tree.contains(&65));
+        let root_key = tree.root().unwrap().borrow().key.unwrap();
+        assert!(root_key == 60 || root_key == 70);
+        check_bst(tree.root());
+
+        assert!(tree.remove(&50));
+        assert!(!tree.remove(&50));
+        check_bst(tree.root());
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![10, 20, 30, 40, 60, 70, 80]);
+        assert_eq!(tree.len(), 7);
+    }
+
+    #[test]
+    fn remove_everything() {
+        let mut tree = SplayTree::new();
+        for key in 0..200 {
+            tree.insert((key * 13) % 200);
+        }
+        for key in 0..200 {
+            assert!(tree.remove(&((key * 7) % 200)));
+            check_bst(tree.root());
+        }
+        assert!(tree.is_empty() && tree.root().is_none());
+    }
+
+    #[test]
+    fn skewed_lookups_are_cheaper_than_plain_bst() {
+        // sorted insert makes the plain BST a list, the hot keys sit at its bottom
+        let mut plain = Bst::new();
+        let mut splay = SplayTree::new();
+        for key in 0..500 {
+            plain.insert(key);
+            splay.insert(key);
+        }
+        splay.reset_stats();
+
+        let trace: Vec<i32> = (0..1000).map(|i| if i % 10 == 0 { i % 500 } else { 495 + i % 5 }).collect();
+        let mut plain_visited = 0;
+        for key in &trace {
+            let node = BstNode::tree_search(plain.root().unwrap(), key).unwrap();
+            plain_visited += BstNode::depth(&node) + 1;
+            assert!(splay.contains(key));
+        }
+        let stats = splay.stats();
+        assert_eq!(stats.operations, trace.len() as u64);
+        let plain_cost = plain_visited as f64 / trace.len() as f64;
+        assert!(stats.amortized_cost() * 4.0 < plain_cost, "{} vs {}", stats.amortized_cost(), plain_cost);
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use crate::structure::bst::{BstNode, BstNodeLink};
+
+/**
+ * Linear congruential generator for test data, the same seed always gives the same
+ * sequence so failures are reproducible
+ */
+pub(crate) struct TestRng {
+    state: u64,
+}
+
+impl TestRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        TestRng { state: seed }
+    }
+
+    // Next value in 0..bound
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        self.state = self.state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.state >> 33) as usize % bound
+    }
+}
+
+// The keys 0..count in a shuffled order
+pub(crate) fn shuffled_keys(count: usize, seed: u64) -> Vec<i32> {
+    let mut rng = TestRng::new(seed);
+    let mut keys: Vec<i32> = (0..count as i32).collect();
+    for i in (1..keys.len()).rev() {
+        keys.swap(i, rng.below(i + 1));
+    }
+    keys
+}
+
+// Assert that every child points back to its parent and the root has no parent
+pub(crate) fn assert_parent_links<K: Ord, V, M>(root: &BstNodeLink<K, V, M>) {
+    assert!(root.borrow().parent.is_none());
+    for node in BstNode::pre_order(root) {
+        for child in [node.borrow().left.clone(), node.borrow().right.clone()].into_iter().flatten() {
+            let parent = BstNode::upgrade_weak_to_strong(child.borrow().parent.clone()).unwrap();
+            assert!(Rc::ptr_eq(&parent, &node));
+        }
+    }
+}
+
+// Assert that every stored subtree size is the node's own count plus its children's sizes
+pub(crate) fn assert_sizes<K: Ord, V, M>(root: &Option<BstNodeLink<K, V, M>>) {
+    if let Some(root) = root {
+        for node in BstNode::pre_order(root) {
+            let size = node.borrow().count + BstNode::subtree_size(&node.borrow().left) + BstNode::subtree_size(&node.borrow().right);
+            assert_eq!(node.borrow().size, size);
+        }
+    }
+}
+
+/**
+ * Check a tree with unique keys: parent links, subtree sizes and strictly increasing
+ * keys in order. Returns the height, -1 for an empty tree
+ */
+pub(crate) fn check_bst<K: Ord + Debug, V, M>(root: Option<&BstNodeLink<K, V, M>>) -> i32 {
+    let root = match root {
+        Some(root) => root,
+        None => return -1,
+    };
+    assert_parent_links(root);
+    assert_sizes(&Some(root.clone()));
+    let nodes: Vec<BstNodeLink<K, V, M>> = BstNode::in_order(root).collect();
+    for pair in nodes.windows(2) {
+        let (left, right) = (pair[0].borrow(), pair[1].borrow());
+        assert!(left.key < right.key, "{:?} is not below {:?}", left.key, right.key);
+    }
+    // the last node of a level-order walk sits on the deepest level
+    let deepest = BstNode::level_order(root).last().unwrap();
+    BstNode::depth(&deepest) as i32
+}
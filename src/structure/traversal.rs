@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+
+use crate::structure::bst::BstNodeLink;
+use crate::structure::tree::NodeLink;
+
+/**
+ * Read access to the children of a node link, shared by `tree::Node` and `bst::BstNode`
+ * so both can use the same traversal iterators.
+ * The iterators only clone `Rc` links, the tree itself is never copied
+ */
+pub trait ChildLinks: Sized {
+    fn left_link(&self) -> Option<Self>;
+    fn right_link(&self) -> Option<Self>;
+}
+
+impl ChildLinks for NodeLink {
+    fn left_link(&self) -> Option<Self> {
+        self.borrow().left.clone()
+    }
+
+    fn right_link(&self) -> Option<Self> {
+        self.borrow().right.clone()
+    }
+}
+
+impl<K, V, M> ChildLinks for BstNodeLink<K, V, M> {
+    fn left_link(&self) -> Option<Self> {
+        self.borrow().left.clone()
+    }
+
+    fn right_link(&self) -> Option<Self> {
+        self.borrow().right.clone()
+    }
+}
+
+// In-order (left, node, right) traversal with an explicit stack of the pending left spine
+pub struct InOrderIter<L> {
+    stack: Vec<L>,
+}
+
+impl<L: ChildLinks> InOrderIter<L> {
+    pub fn new(root: Option<L>) -> Self {
+        let mut iter = InOrderIter { stack: Vec::new() };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut node: Option<L>) {
+        while let Some(exist) = node {
+            node = exist.left_link();
+            self.stack.push(exist);
+        }
+    }
+}
+
+impl<L: ChildLinks> Iterator for InOrderIter<L> {
+    type Item = L;
+
+    fn next(&mut self) -> Option<L> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(node.right_link());
+        Some(node)
+    }
+}
+
+// Pre-order (node, left, right) traversal
+pub struct PreOrderIter<L> {
+    stack: Vec<L>,
+}
+
+impl<L: ChildLinks> PreOrderIter<L> {
+    pub fn new(root: Option<L>) -> Self {
+        PreOrderIter { stack: root.into_iter().collect() }
+    }
+}
+
+impl<L: ChildLinks> Iterator for PreOrderIter<L> {
+    type Item = L;
+
+    fn next(&mut self) -> Option<L> {
+        let node = self.stack.pop()?;
+        // right goes first so the left subtree is popped first
+        self.stack.extend(node.right_link());
+        self.stack.extend(node.left_link());
+        Some(node)
+    }
+}
+
+/**
+ * Post-order (left, right, node) traversal
+ * Each stack entry remembers whether its children were already pushed
+ */
+pub struct PostOrderIter<L> {
+    stack: Vec<(L, bool)>,
+}
+
+impl<L: ChildLinks> PostOrderIter<L> {
+    pub fn new(root: Option<L>) -> Self {
+        PostOrderIter { stack: root.into_iter().map(|node| (node, false)).collect() }
+    }
+}
+
+impl<L: ChildLinks> Iterator for PostOrderIter<L> {
+    type Item = L;
+
+    fn next(&mut self) -> Option<L> {
+        loop {
+            let (node, expanded) = self.stack.pop()?;
+            if expanded {
+                return Some(node);
+            }
+            let right = node.right_link();
+            let left = node.left_link();
+            self.stack.push((node, true));
+            self.stack.extend(right.map(|child| (child, false)));
+            self.stack.extend(left.map(|child| (child, false)));
+        }
+    }
+}
+
+// Level-order (breadth first) traversal
+pub struct LevelOrderIter<L> {
+    queue: VecDeque<L>,
+}
+
+impl<L: ChildLinks> LevelOrderIter<L> {
+    pub fn new(root: Option<L>) -> Self {
+        LevelOrderIter { queue: root.into_iter().collect() }
+    }
+}
+
+impl<L: ChildLinks> Iterator for LevelOrderIter<L> {
+    type Item = L;
+
+    fn next(&mut self) -> Option<L> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.left_link());
+        self.queue.extend(node.right_link());
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structure::bst::{BstNode, BstNodeLink};
+    use crate::structure::tree::{Node, NodeLink};
+
+    //        5
+    //      /   \
+    //     3     7
+    //    / \     \
+    //   2   4     10
+    fn sample_tree() -> NodeLink {
+        let rootlink = Node::new_nodelink(5);
+        rootlink.borrow_mut().add_left_child(&rootlink, 3);
+        rootlink.borrow_mut().add_right_child(&rootlink, 7);
+        if let Some(left_tree) = &rootlink.borrow().left {
+            left_tree.borrow_mut().add_left_child(left_tree, 2);
+            left_tree.borrow_mut().add_right_child(left_tree, 4);
+        }
+        if let Some(right_tree) = &rootlink.borrow().right {
+            right_tree.borrow_mut().add_right_child(right_tree, 10);
+        }
+        rootlink
+    }
+
+    fn values(nodes: impl Iterator<Item = NodeLink>) -> Vec<i32> {
+        nodes.map(|node| node.borrow().value).collect()
+    }
+
+    fn keys(nodes: impl Iterator<Item = BstNodeLink>) -> Vec<i32> {
+        nodes.map(|node| node.borrow().key.unwrap()).collect()
+    }
+
+    #[test]
+    fn node_traversal_orders() {
+        let root = sample_tree();
+        assert_eq!(values(Node::in_order(&root)), vec![2, 3, 4, 5, 7, 10]);
+        assert_eq!(values(Node::pre_order(&root)), vec![5, 3, 2, 4, 7, 10]);
+        assert_eq!(values(Node::post_order(&root)), vec![2, 4, 3, 10, 7, 5]);
+        assert_eq!(values(Node::level_order(&root)), vec![5, 3, 7, 2, 4, 10]);
+    }
+
+    #[test]
+    fn bst_traversal_orders() {
+        let mut root: Option<BstNodeLink> = None;
+        for key in [5, 3, 7, 2, 4, 10] {
+            BstNode::insert(&mut root, key);
+        }
+        let root = root.unwrap();
+        assert_eq!(keys(BstNode::in_order(&root)), vec![2, 3, 4, 5, 7, 10]);
+        assert_eq!(keys(BstNode::pre_order(&root)), vec![5, 3, 2, 4, 7, 10]);
+        assert_eq!(keys(BstNode::post_order(&root)), vec![2, 4, 3, 10, 7, 5]);
+        assert_eq!(keys(BstNode::level_order(&root)), vec![5, 3, 7, 2, 4, 10]);
+
+        // the yielded links are the tree's own nodes
+        let mut total = 0;
+        for node in BstNode::pre_order(&root) {
+            total += node.borrow().key.unwrap();
+        }
+        assert_eq!(total, 31);
+    }
+}
@@ -0,0 +1,236 @@
+use std::rc::Rc;
+
+use crate::structure::bst::{BstNode, BstNodeLink};
+use crate::tool::DotNodeStyle;
+
+// Random heap priority of a treap node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Priority(pub u64);
+
+// Treap nodes are drawn like plain BST nodes
+impl DotNodeStyle for Priority {}
+
+/**
+ * Treap nodes are `BstNode`s whose metadata is a random priority,
+ * the tree is a BST on the keys and a max-heap on the priorities
+ */
+pub type TreapNodeLink<K = i32> = BstNodeLink<K, (), Priority>;
+
+// Seed used by `Treap::new`, so two runs without an explicit seed build the same shape
+pub const DEFAULT_SEED: u64 = 0x5EED;
+
+/**
+ * SplitMix64 generator, small and good enough for priorities
+ * The same seed always gives the same sequence
+ */
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/**
+ * Randomized BST: a new key gets a priority from the seeded generator and is rotated up
+ * while it beats its parent, a removed key is rotated down until it has at most one child
+ * and is then unlinked with `BstNode::delete`. Keys are unique
+ */
+#[derive(Debug)]
+pub struct Treap<K = i32> {
+    root: Option<TreapNodeLink<K>>,
+    len: usize,
+    rng: SplitMix64,
+}
+
+impl<K: Ord> Treap<K> {
+    // Empty treap seeded with DEFAULT_SEED
+    pub fn new() -> Self {
+        Treap::with_seed(DEFAULT_SEED)
+    }
+
+    // Empty treap with its own seed, equal seeds and equal operations give equal trees
+    pub fn with_seed(seed: u64) -> Self {
+        Treap {
+            root: None,
+            len: 0,
+            rng: SplitMix64::new(seed),
+        }
+    }
+
+    // Root of the tree, e.g. for tree_search or generate_dotfile_bst
+    pub fn root(&self) -> Option<&TreapNodeLink<K>> {
+        self.root.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Insert a key, returns false if it was already in the treap
+    pub fn insert(&mut self, key: K) -> bool {
+        let node = match BstNode::insert_unique(&mut self.root, key, ()) {
+            Ok(new_node) => new_node,
+            Err(_) => return false,
+        };
+        node.borrow_mut().meta = Priority(self.rng.next_u64());
+
+        // rotate the new node up while its priority beats its parent's
+        loop {
+            let parent = BstNode::upgrade_weak_to_strong(node.borrow().parent.clone());
+            let parent = match parent {
+                Some(parent) if parent.borrow().meta < node.borrow().meta => parent,
+                _ => break,
+            };
+            let is_left = parent.borrow().left.as_ref().is_some_and(|left| Rc::ptr_eq(left, &node));
+            if is_left {
+                BstNode::rotate_right(&mut self.root, &parent);
+            } else {
+                BstNode::rotate_left(&mut self.root, &parent);
+            }
+        }
+        self.len += 1;
+        true
+    }
+
+    // Remove a key, returns false if it is not in the treap
+    pub fn remove(&mut self, key: &K) -> bool {
+        let z = match self.search(key) {
+            Some(node) => node,
+            None => return false,
+        };
+        // rotate z down, always lifting the child with the higher priority, until it has
+        // at most one child and delete is a plain transplant
+        loop {
+            let left = z.borrow().left.clone();
+            let right = z.borrow().right.clone();
+            match (left, right) {
+                (Some(left), Some(right)) => {
+                    if left.borrow().meta > right.borrow().meta {
+                        BstNode::rotate_right(&mut self.root, &z);
+                    } else {
+                        BstNode::rotate_left(&mut self.root, &z);
+                    }
+                }
+                _ => break,
+            }
+        }
+        BstNode::delete(&mut self.root, &z);
+        self.len -= 1;
+        true
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.search(key).is_some()
+    }
+
+    // Live node holding key, ready for tree_successor
+    pub fn search(&self, key: &K) -> Option<TreapNodeLink<K>> {
+        BstNode::tree_search(self.root.as_ref()?, key)
+    }
+
+    // All keys in order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = K>
+    where
+        K: Clone,
+    {
+        let nodes = self.root.as_ref().map(BstNode::in_order);
+        nodes.into_iter().flatten().map(|node| node.borrow().key.clone().unwrap())
+    }
+
+    // Drop every node, the generator keeps going from where it was
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+}
+
+impl<K: Ord> Default for Treap<K> {
+    fn default() -> Self {
+        Treap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::test_util::check_bst;
+    use crate::tool::generate_dotfile_bst;
+
+    // Check ordering, links and sizes and the heap order on priorities, returns the height
+    fn check(treap: &Treap) -> i32 {
+        let height = check_bst(treap.root());
+        if let Some(root) = treap.root() {
+            for node in BstNode::pre_order(root) {
+                for child in [node.borrow().left.clone(), node.borrow().right.clone()].into_iter().flatten() {
+                    assert!(child.borrow().meta <= node.borrow().meta);
+                }
+            }
+        }
+        height
+    }
+
+    fn dot_snapshot(treap: &Treap, name: &str) -> String {
+        let dot_path = std::env::temp_dir().join(name);
+        generate_dotfile_bst(treap.root().unwrap(), dot_path.to_str().unwrap());
+        std::fs::read_to_string(&dot_path).unwrap()
+    }
+
+    #[test]
+    fn sorted_input_stays_shallow_and_heap_ordered() {
+        let mut treap = Treap::with_seed(42);
+        for key in 0..2000 {
+            assert!(treap.insert(key));
+        }
+        assert!(!treap.insert(7));
+        let height = check(&treap);
+        assert!(height < 60, "treap height {} is too deep", height);
+        assert_eq!(treap.iter().collect::<Vec<_>>(), (0..2000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn remove_keeps_heap_order() {
+        let mut treap = Treap::new();
+        for key in 0..300 {
+            treap.insert((key * 37) % 300);
+        }
+        for key in (0..300).step_by(2) {
+            assert!(treap.remove(&key));
+            assert!(!treap.contains(&key));
+        }
+        check(&treap);
+        assert_eq!(treap.len(), 150);
+        assert_eq!(BstNode::subtree_size(&treap.root().cloned()), 150);
+        assert!(!treap.remove(&0));
+    }
+
+    #[test]
+    fn same_seed_same_dotfile() {
+        let build = |seed: u64| {
+            let mut treap = Treap::with_seed(seed);
+            for key in [50, 20, 80, 10, 30, 70, 90, 60] {
+                treap.insert(key);
+            }
+            treap.remove(&20);
+            treap
+        };
+        let first = dot_snapshot(&build(7), "treap_seed_a.dot");
+        let second = dot_snapshot(&build(7), "treap_seed_b.dot");
+        assert_eq!(first, second);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::structure::tree::NodeLink;
 use crate::structure::bst::BstNodeLink;
+use std::fmt::Display;
 use std::fs::File;
 use std::io::Write;
 
@@ -23,26 +24,26 @@ pub fn generate_dotfile(root: &NodeLink, output_path: &str){
 /**
  * We will print string as we traverse, node by node
  * at most a line per node printing, e.g: a--b;
- * traversal mode in BFS
+ * traversal mode in pre-order, with an explicit stack so deep trees cannot overflow
  */
 fn node_traversal(node: &NodeLink) -> String{
     let mut new_info: String = "".to_string();
-    //we print the child nodes first
-    let left_child = &node.borrow().left;
-    //won't print anything if left child is None
-    new_info += &print_child(&node, left_child.as_ref());
-    let right_child = &node.borrow().right;
-    new_info += &print_child(&node, right_child.as_ref());
-    //now we need to traverse deeper
-    if left_child.is_some(){
-        new_info += &node_traversal(&left_child.as_ref().unwrap());
+    let mut stack = vec![node.clone()];
+    while let Some(node) = stack.pop() {
+        //we print the child nodes first
+        let left_child = node.borrow().left.clone();
+        //won't print anything if left child is None
+        new_info += &print_child(&node, left_child.as_ref());
+        let right_child = node.borrow().right.clone();
+        new_info += &print_child(&node, right_child.as_ref());
+        //now we need to traverse deeper, the left subtree is popped first
+        stack.extend(right_child);
+        stack.extend(left_child);
     }
-    if right_child.is_some(){
-        new_info += &node_traversal(&right_child.as_ref().unwrap());
-    }
-    return new_info;
+    new_info
 }
 
+#[allow(clippy::needless_return)]
 fn print_child(parent_node: &NodeLink, child_node: Option<&NodeLink>) -> String{
     let mut new_info = "".to_string();
     if let Some(child) = child_node {
@@ -57,7 +58,20 @@ fn print_child(parent_node: &NodeLink, child_node: Option<&NodeLink>) -> String{
     return new_info;
 }
 
-pub fn generate_dotfile_bst(root: &BstNodeLink, output_path: &str){
+/**
+ * Extra graphviz attributes for a node, picked from the node metadata
+ * e.g. the red-black tree renders its red nodes in red
+ */
+pub trait DotNodeStyle {
+    fn dot_style(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+// plain BST nodes
+impl DotNodeStyle for () {}
+
+pub fn generate_dotfile_bst<K: Display, V, M: DotNodeStyle>(root: &BstNodeLink<K, V, M>, output_path: &str){
     let graph_name = " tree";
     let preamble = "graph".to_owned() + graph_name + "{\n";
     let epilogue = "}";
@@ -68,40 +82,105 @@ pub fn generate_dotfile_bst(root: &BstNodeLink, output_path: &str){
     let mut output = File::create(output_path).expect("Failed to create");
     let _ = output.write_all(final_text.as_bytes());}
 
-fn node_traversal_bst(node: &BstNodeLink) -> String{
+fn node_traversal_bst<K: Display, V, M: DotNodeStyle>(node: &BstNodeLink<K, V, M>) -> String{
     let mut new_info: String = "".to_string();
-    //we print the child nodes first
-    let left_child = &node.borrow().left;
-    //won't print anything if left child is None
-    new_info += &print_child_bst(&node, left_child.as_ref());
-    let right_child = &node.borrow().right;
-    new_info += &print_child_bst(&node, right_child.as_ref());
-    //now we need to traverse deeper
-    if left_child.is_some(){
-        new_info += &node_traversal_bst(&left_child.as_ref().unwrap());
-    }
-    if right_child.is_some(){
-        new_info += &node_traversal_bst(&right_child.as_ref().unwrap());
+    let mut stack = vec![node.clone()];
+    while let Some(node) = stack.pop() {
+        //styled nodes get their own line, e.g: a [color=red];
+        if let Some(style) = node.borrow().meta.dot_style() {
+            new_info += "\t";
+            new_info += &dot_id(node.borrow().key.as_ref().unwrap());
+            new_info += " [";
+            new_info += style;
+            new_info += "];\n";
+        }
+        //we print the child nodes first
+        let left_child = node.borrow().left.clone();
+        //won't print anything if left child is None
+        new_info += &print_child_bst(&node, left_child.as_ref());
+        let right_child = node.borrow().right.clone();
+        new_info += &print_child_bst(&node, right_child.as_ref());
+        //now we need to traverse deeper, same pre-order as before with an explicit stack
+        stack.extend(right_child);
+        stack.extend(left_child);
     }
-    return new_info;
+    new_info
 }
 
-fn print_child_bst(parent_node: &BstNodeLink, child_node: Option<&BstNodeLink>) -> String{
+fn print_child_bst<K: Display, V, M>(parent_node: &BstNodeLink<K, V, M>, child_node: Option<&BstNodeLink<K, V, M>>) -> String{
     let mut new_info = "".to_string();
     if let Some(child) = child_node {
         //concat parent
         new_info += "\t";
-        new_info += &parent_node.borrow().key.unwrap().to_string();
+        new_info += &dot_id(parent_node.borrow().key.as_ref().unwrap());
         //next_info += node.borrow().parent.unwrap().value;
         new_info += "--";
-        new_info += &child.borrow().key.unwrap().to_string();
+        new_info += &dot_id(child.borrow().key.as_ref().unwrap());
         new_info += ";\n";
     }
-    return new_info;
+    new_info
+}
+
+/**
+ * Graphviz node id for a key: DOT numerals and plain identifiers are written as they are,
+ * anything else (e.g. an interval "[1, 5]", "1e5" or a keyword like "node") is quoted
+ */
+fn dot_id<K: Display>(key: &K) -> String {
+    let text = key.to_string();
+    if is_dot_numeral(&text) || is_dot_identifier(&text) {
+        return text;
+    }
+    "\"".to_owned() + &text.replace('\\', "\\\\").replace('"', "\\\"") + "\""
+}
+
+// DOT numeral: -?(.[0-9]+ | [0-9]+(.[0-9]*)?)
+fn is_dot_numeral(text: &str) -> bool {
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+    let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    match fraction {
+        None => !whole.is_empty() && all_digits(whole),
+        Some(fraction) => all_digits(whole) && all_digits(fraction) && !(whole.is_empty() && fraction.is_empty()),
+    }
+}
+
+// DOT identifier: letters, digits and underscores not starting with a digit, keywords excluded
+fn is_dot_identifier(text: &str) -> bool {
+    const KEYWORDS: [&str; 6] = ["node", "edge", "graph", "digraph", "subgraph", "strict"];
+    !text.is_empty()
+        && !text.starts_with(|c: char| c.is_ascii_digit())
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(text))
 }
 
 /*
 pub fn graph_dotfile_string(root: &NodeLink) -> String{
     ""
 }
-*/
\ No newline at end of file
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_ids_follow_the_dot_grammar() {
+        for plain in ["15", "-3", "2.5", ".5", "7.", "-.25", "abc", "_x1", "m"] {
+            assert_eq!(dot_id(&plain), plain);
+        }
+        assert_eq!(dot_id(&1e5), "100000");
+        assert_eq!(dot_id(&"1e5"), "\"1e5\"");
+        assert_eq!(dot_id(&"+5"), "\"+5\"");
+        assert_eq!(dot_id(&"inf"), "inf");
+        assert_eq!(dot_id(&"NaN"), "NaN");
+        assert_eq!(dot_id(&"-"), "\"-\"");
+        assert_eq!(dot_id(&"."), "\".\"");
+        assert_eq!(dot_id(&"1.2.3"), "\"1.2.3\"");
+        assert_eq!(dot_id(&"Node"), "\"Node\"");
+        assert_eq!(dot_id(&"a b"), "\"a b\"");
+        assert_eq!(dot_id(&"say \"hi\" \\"), "\"say \\\"hi\\\" \\\\\"");
+    }
+}